@@ -0,0 +1,285 @@
+//! Structural analysis of frames, such as of granular packings: the packing fraction,
+//! coordination numbers, the radial distribution function g(r), and the nematic order of
+//! spherocylinders.
+
+use na;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashMap};
+use std::f32::consts::PI;
+
+use contacts::{find_contacts, neighbour_pairs};
+use objects::{Frame, ObjectID, SimBox, EPSILON};
+
+#[cfg(test)]
+use objects::{Sphere, Spherocylinder};
+#[cfg(test)]
+use toml;
+
+/// Volume of a sphere of diameter `diameter`
+pub fn sphere_volume(diameter: f32) -> f32 {
+    PI * diameter.powi(3) / 6.
+}
+
+/// Volume of a spherocylinder of diameter `diameter`, `length` long from cap to cap
+pub fn spherocylinder_volume(diameter: f32, length: f32) -> f32 {
+    let core = (length - diameter).max(0.);
+    sphere_volume(diameter) + PI * diameter * diameter / 4. * core
+}
+
+/// Total volume of the particles of a frame, ignoring any overlaps
+pub fn particle_volume(frame: &Frame) -> f32 {
+    let spheres: f32 = frame
+        .spheres
+        .iter()
+        .map(|s| sphere_volume(s.diameter))
+        .sum();
+    let spherocylinders: f32 = frame
+        .spherocylinders
+        .iter()
+        .map(|s| spherocylinder_volume(s.diameter, s.get_axis().norm()))
+        .sum();
+    spheres + spherocylinders
+}
+
+/// The fraction of the box filled by the particles
+pub fn packing_fraction(frame: &Frame, sim_box: &SimBox) -> f32 {
+    particle_volume(frame) / sim_box.volume().max(EPSILON)
+}
+
+/// The number of contacts of each particle, as found by `contacts::find_contacts`, in the
+/// order of the frame: spheres, then spherocylinders.
+pub fn coordination(
+    frame: &Frame,
+    tolerance: f32,
+    sim_box: Option<&SimBox>,
+) -> Vec<(ObjectID, usize)> {
+    let mut counts: HashMap<ObjectID, usize> = HashMap::new();
+    for contact in find_contacts(frame, tolerance, sim_box) {
+        let (a, b) = contact.between;
+        *counts.entry(a).or_insert(0) += 1;
+        *counts.entry(b).or_insert(0) += 1;
+    }
+    let names = frame
+        .spheres
+        .iter()
+        .map(|s| &s.names)
+        .chain(frame.spherocylinders.iter().map(|s| &s.names));
+    names
+        .map(|n| (n.clone(), counts.get(n).cloned().unwrap_or(0)))
+        .collect()
+}
+
+/// The radial distribution function g(r) of the particle centres, in `bins` bins out to
+/// `r_max`, as (middle of the bin, g) pairs. The density is that of the other particles in the
+/// box; in a box that is not periodic, g falls off at distances comparable to the box, as fewer
+/// neighbours fit.
+pub fn radial_distribution(
+    frame: &Frame,
+    sim_box: &SimBox,
+    bins: usize,
+    r_max: f32,
+) -> Vec<(f32, f32)> {
+    let centres: Vec<na::Point3<f32>> = frame
+        .spheres
+        .iter()
+        .map(|s| na::Point3::from(s.x()))
+        .chain(
+            frame
+                .spherocylinders
+                .iter()
+                .map(|s| na::Point3::from(s.x())),
+        )
+        .collect();
+    let width = r_max / bins.max(1) as f32;
+    let mut counts = vec![0usize; bins];
+    for (_, _, separation) in neighbour_pairs(&centres, r_max, Some(sim_box)) {
+        let bin = (separation.norm() / width) as usize;
+        if bin < bins {
+            counts[bin] += 1;
+        }
+    }
+
+    let n = centres.len() as f32;
+    // Each particle sees the other n - 1, so g tends to 1 at large r
+    let density = (n - 1.).max(0.) / sim_box.volume().max(EPSILON);
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let (inner, outer) = (i as f32 * width, (i + 1) as f32 * width);
+            let shell = 4. / 3. * PI * (outer.powi(3) - inner.powi(3));
+            // Each pair is counted once, but is a neighbour of both particles
+            let ideal = n * density * shell;
+            let g = if ideal > 0. {
+                2. * count as f32 / ideal
+            } else {
+                0.
+            };
+            ((inner + outer) / 2., g)
+        })
+        .collect()
+}
+
+/// How well the spherocylinders of a frame line up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NematicOrder {
+    /// The order parameter S, from 0 for random directions to 1 for all parallel
+    pub order: f32,
+    /// The direction they line up along, of unit length; its sign has no meaning
+    pub director: na::Vector3<f32>,
+}
+
+/// The nematic order of the axes of the spherocylinders of a frame, from the largest
+/// eigenvalue of the Q tensor, or None if there are no spherocylinders.
+pub fn nematic_order(frame: &Frame) -> Option<NematicOrder> {
+    let axes: Vec<na::Vector3<f32>> = frame
+        .spherocylinders
+        .iter()
+        .filter_map(|s| s.get_axis().try_normalize(EPSILON))
+        .collect();
+    if axes.is_empty() {
+        return None;
+    }
+    let sum = axes
+        .iter()
+        .fold(na::Matrix3::zeros(), |q: na::Matrix3<f32>, u| {
+            q + u * u.transpose() * 1.5 - na::Matrix3::identity() * 0.5
+        });
+    let q = sum / axes.len() as f32;
+    let eigen = na::SymmetricEigen::new(q);
+    let largest = eigen.eigenvalues.imax();
+    Some(NematicOrder {
+        order: eigen.eigenvalues[largest],
+        director: eigen.eigenvectors.column(largest).into_owned(),
+    })
+}
+
+/// Which statistics are computed for each frame, and how
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct AnalysisConfig {
+    /// Largest gap between two particles still counted as a contact
+    pub tolerance: f32,
+    /// Number of bins of g(r)
+    pub bins: usize,
+    /// Largest distance of g(r). None uses half the shortest side of the box.
+    pub r_max: Option<f32>,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        AnalysisConfig {
+            tolerance: 0.,
+            bins: 100,
+            r_max: None,
+        }
+    }
+}
+
+impl AnalysisConfig {
+    /// Largest distance of g(r) in a box
+    pub fn r_max(&self, sim_box: &SimBox) -> f32 {
+        let (x, y, z) = sim_box.size;
+        self.r_max.unwrap_or_else(|| x.min(y).min(z) / 2.)
+    }
+
+    /// Statistics of a frame, by name, to add to its `values`: `packing_fraction` (given a
+    /// box), the mean coordination number `coordination`, and `nematic_order` (given
+    /// spherocylinders).
+    pub fn values(&self, frame: &Frame, sim_box: Option<&SimBox>) -> BTreeMap<String, f64> {
+        let mut values = BTreeMap::new();
+        if let Some(b) = sim_box {
+            let _ = values.insert("packing_fraction".into(), packing_fraction(frame, b) as f64);
+        }
+        let counts = coordination(frame, self.tolerance, sim_box);
+        if !counts.is_empty() {
+            let total: usize = counts.iter().map(|&(_, n)| n).sum();
+            let mean = total as f64 / counts.len() as f64;
+            let _ = values.insert("coordination".into(), mean);
+        }
+        if let Some(nematic) = nematic_order(frame) {
+            let _ = values.insert("nematic_order".into(), nematic.order as f64);
+        }
+        values
+    }
+
+    /// Add the statistics of each frame to its `values`, replacing any of the same names
+    pub fn add_values(&self, frames: &mut [Frame], sim_box: Option<&SimBox>) {
+        for frame in frames {
+            let values = self.values(frame, sim_box);
+            frame.values.extend(values);
+        }
+    }
+}
+
+#[test]
+fn frame_analysis() {
+    let names = |n: &str| ObjectID(vec![n.into()]);
+    let sphere = |n: &str, loc: (f32, f32, f32)| Sphere {
+        loc,
+        diameter: 1.,
+        names: names(n),
+        vectors: BTreeMap::new(),
+    };
+    let rod = |n: &str, axis: (f32, f32, f32)| Spherocylinder {
+        loc: (0., 3., 0.),
+        axis,
+        diameter: 0.5,
+        names: names(n),
+        vectors: BTreeMap::new(),
+    };
+    let frame = Frame {
+        spheres: vec![
+            sphere("a", (0., 0., 0.)),
+            sphere("b", (1., 0., 0.)),
+            sphere("c", (-1., 0., 0.)),
+        ],
+        spherocylinders: vec![rod("x", (2., 0., 0.)), rod("y", (-2., 0., 0.))],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![],
+    };
+    let sim_box = SimBox {
+        size: (10., 10., 10.),
+        periodic: true,
+    };
+
+    let rods = 2. * spherocylinder_volume(0.5, 2.);
+    assert!((rods - 2. * (PI / 48. + PI / 16. * 1.5)).abs() < 1e-5);
+    let phi = packing_fraction(&frame, &sim_box);
+    assert!((phi - (PI / 2. + rods) / 1000.).abs() < 1e-6);
+
+    let counts = coordination(&frame, 0., None);
+    assert_eq!(counts[0], (names("a"), 2));
+    assert_eq!(counts[1], (names("b"), 1));
+    // The two rods lie on top of each other
+    assert_eq!(counts[3], (names("x"), 1));
+
+    let nematic = nematic_order(&frame).unwrap();
+    assert!((nematic.order - 1.).abs() < 1e-5);
+    assert!((nematic.director.x.abs() - 1.).abs() < 1e-5);
+    let crossed = Frame {
+        spherocylinders: vec![rod("x", (2., 0., 0.)), rod("y", (0., 2., 0.))],
+        ..frame.clone()
+    };
+    // Two perpendicular rods: S = -1/2 along the third axis, 1/4 in their plane
+    assert!((nematic_order(&crossed).unwrap().order - 0.25).abs() < 1e-5);
+
+    let config: AnalysisConfig = toml::from_str("bins = 10").unwrap();
+    assert_eq!(config.r_max(&sim_box), 5.);
+    let values = config.values(&frame, Some(&sim_box));
+    assert_eq!(values["coordination"], 6. / 5.);
+    assert!((values["nematic_order"] - 1.).abs() < 1e-5);
+
+    // g(r): the spheres are 1 and 2 apart, and the rods 3 or more from each
+    let g = radial_distribution(&frame, &sim_box, 10, 5.);
+    assert_eq!(g.len(), 10);
+    assert_eq!(g[0].0, 0.25);
+    assert_eq!(g[1].1, 0.);
+    let density = 4. / 1000.;
+    let shell = 4. / 3. * PI * (1.5f32.powi(3) - 1.);
+    assert!((g[2].1 - 2. * 2. / (5. * density * shell)).abs() < 1e-3);
+}
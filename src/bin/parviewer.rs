@@ -16,25 +16,32 @@ use docopt::Docopt;
 use serde::Deserialize;
 
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use parview::{misc, Color, Config, Frame, Palette, Parviewer, TomlConfig, EPSILON};
+use parview::{misc, Config, Frame, Palette, Parviewer, TomlConfig, EPSILON};
 use std::f32::consts::PI;
 
 // Write the Docopt usage string.
 const USAGE: &str = "
-Usage: parview [options] [--] [<file>]
+Usage: parview [options] [--] [<file>...]
 
 Options:
     -h, --help              Help and usage
     -g, --generate          Generate test_frames.json
     -p, --palette FILE      Use palette file (toml file), instead of default.
     -c, --config FILE       Use config file (toml file), instead of default.
+    --snapshot FRAME        Save an image of frame FRAME (from 0), and exit.
+    --out FILE              File for the --snapshot image, instead of the
+                            configured snapshot directory and file name.
+    --reference FILE        Color particles by how far they are from where they
+                            are in the same frame of FILE.
 
 
 Arguments:
     <file>      json file representing the frames. json.gz also accepted,
-                if the extension is \".gz\".
+                if the extension is \".gz\". With more than one, the
+                trajectories are shown side by side, or as set in the
+                config's [viewports] section.
 ";
 
 #[derive(Debug, Deserialize)]
@@ -42,7 +49,10 @@ struct Args {
     flag_palette: Option<String>,
     flag_config: Option<String>,
     flag_generate: bool,
-    arg_file: Option<String>,
+    flag_snapshot: Option<usize>,
+    flag_out: Option<String>,
+    flag_reference: Option<String>,
+    arg_file: Vec<String>,
 }
 
 fn run() -> Result<(), Box<dyn Error>> {
@@ -59,8 +69,8 @@ fn run() -> Result<(), Box<dyn Error>> {
 
     let config: Config = toml_config.to_parviewer_config();
 
-    let fname: &str = match args.arg_file {
-        Some(ref s) => s,
+    let fname: &str = match args.arg_file.first() {
+        Some(s) => s,
         None => "test_frame.json",
     };
     let path: &Path = Path::new(fname);
@@ -89,9 +99,35 @@ fn run() -> Result<(), Box<dyn Error>> {
 
     // println!("config: {:?}", config);
 
-    let mut viewer = Parviewer::new(frames, palette, config)?;
+    let first_palette = toml_config.viewports.palette(0, &palette)?;
+    let mut viewer = Parviewer::new(frames, first_palette, config)?;
+    viewer.label = misc::file_label(fname);
+    for (i, fname) in args.arg_file.iter().enumerate().skip(1) {
+        let frames: Vec<Frame> = misc::deserialize_by_ext(Path::new(fname))?;
+        let palette = toml_config.viewports.palette(i, &palette)?;
+        viewer.add_trajectory(&misc::file_label(fname), frames, palette)?;
+    }
+    if let Some(ref fname) = args.flag_reference {
+        viewer.set_reference(misc::deserialize_by_ext(Path::new(fname))?)?;
+    }
+
+    if let Some(index) = args.flag_snapshot {
+        let out = match args.flag_out {
+            Some(ref fname) => PathBuf::from(fname),
+            None => {
+                // Named by the frame asked for, not the nearest in the playback range
+                viewer.timer.show_index(index);
+                viewer.snapshot_path()
+            }
+        };
+        viewer.render_snapshot(index, &out)?;
+        println!("Saved image to {}", out.display());
+        return Ok(());
+    }
+
     let _ = viewer.timer.at_least(toml_config.fps);
-    let text_color = Color(255, 255, 255);
+    let text_style = viewer.get_config().text.clone();
+    let (text_x, text_y) = text_style.position;
 
     viewer.run(|viewer, _| {
         if toml_config.rotate.abs() > EPSILON {
@@ -99,7 +135,7 @@ fn run() -> Result<(), Box<dyn Error>> {
             viewer.camera.set_yaw(new_yaw);
         }
 
-        viewer.draw_frame_text(0., 0., text_color);
+        viewer.draw_frame_text(text_x, text_y, text_style.color);
 
         let dt = viewer.timer.get_dt();
         let dt_text = if dt >= 0.6 || dt.abs() < 1e-6 || dt <= -0.6 {
@@ -110,14 +146,18 @@ fn run() -> Result<(), Box<dyn Error>> {
             format!("-1/{}", -1. / dt)
         };
 
-        let text = format!(
-            "t:{:6.2}, dt:{}, coloring: {}",
-            viewer.timer.get_time(),
-            dt_text,
-            viewer.palette.partials_string()
-        );
+        let time_text = match viewer.timer.sim_time() {
+            Some(time) => format!("time:{:8.3}", time),
+            None => format!("t:{:6.2}", viewer.timer.get_time()),
+        };
 
-        viewer.draw_text(&*text, 0., 1., text_color);
+        let coloring = match viewer.cluster_stats {
+            Some(stats) => stats.describe(),
+            None => viewer.palette.partials_string(),
+        };
+        let text = format!("{}, dt:{}, coloring: {}", time_text, dt_text, coloring);
+
+        viewer.draw_text(&*text, 0., 1., text_style.color);
     });
     Ok(())
 }
@@ -125,8 +165,6 @@ fn run() -> Result<(), Box<dyn Error>> {
 /// The main entry point.
 pub fn main() {
     if let Err(err) = run() {
-        println!("ERROR.");
-
-        misc::err_print(&*err);
+        misc::exit_with_error(&*err);
     }
 }
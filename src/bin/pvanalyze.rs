@@ -0,0 +1,222 @@
+//! # ParView
+//!
+#![deny(non_camel_case_types)]
+#![deny(unused_parens)]
+#![deny(non_upper_case_globals)]
+#![deny(unused_qualifications)]
+#![deny(missing_docs)]
+#![deny(unused_results)]
+
+extern crate docopt;
+extern crate serde;
+
+extern crate parview;
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use parview::analysis;
+use parview::{misc, Frame, ObjectID, SimBox, TomlConfig};
+
+// Write the Docopt usage string.
+const USAGE: &str = "
+Usage: pvanalyze [options] [--] <particlefile> [<csvfile>]
+
+Write statistics of each frame as CSV, to <csvfile> or else to the terminal: the
+packing fraction (given a box), the mean coordination number, and the nematic
+order of the spherocylinders.
+
+Options:
+    -h, --help              Help and usage
+    -c, --config FILE       Use config file (toml file), for its [box] and
+                            [analysis] sections.
+    --box SIZE              Box around the particles, centred on the origin, as
+                            a side length or \"LX,LY,LZ\", instead of the config's.
+    --periodic              The box is periodic.
+    --tolerance GAP         Largest gap still counted as a contact, instead of the
+                            config's.
+    --particles             Write the coordination number of each particle instead.
+    --rdf                   Write the radial distribution function g(r) instead,
+                            averaged over the frames. Needs a box.
+    --bins N                Number of bins of g(r), instead of the config's.
+    --rmax R                Largest distance of g(r), instead of half the box.
+    --start N               First frame to analyze, instead of the config's start.
+    --end N                 End of the range (exclusive), instead of the config's end.
+    --stride N              Analyze every N-th frame, instead of the config's stride.
+
+
+Arguments:
+    <particlefile>  json file representing the frames. json.gz also accepted, if
+                    the extension is \".gz\".
+    <csvfile>       File to write the CSV to. Without it, it is written to the
+                    terminal.
+";
+
+#[derive(Deserialize)]
+struct Args {
+    flag_config: Option<String>,
+    flag_box: Option<String>,
+    flag_periodic: bool,
+    flag_tolerance: Option<f32>,
+    flag_particles: bool,
+    flag_rdf: bool,
+    flag_bins: Option<usize>,
+    flag_rmax: Option<f32>,
+    flag_start: Option<usize>,
+    flag_end: Option<usize>,
+    flag_stride: Option<usize>,
+    arg_particlefile: String,
+    arg_csvfile: Option<String>,
+}
+
+/// Parse a box size: one side length, or three separated by commas
+fn parse_box(s: &str) -> Result<(f32, f32, f32), Box<dyn std::error::Error>> {
+    let sides = s
+        .split(',')
+        .map(|side| side.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()?;
+    match sides[..] {
+        [l] => Ok((l, l, l)),
+        [x, y, z] => Ok((x, y, z)),
+        _ => Err(format!("A box is one side length, or three; not '{}'", s).into()),
+    }
+}
+
+/// A field of a CSV file, quoted if needed
+fn csv_field(s: &str) -> String {
+    if s.contains(|c: char| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// A frame's time or step, or nothing if it has none
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let docopt = docopt::Docopt::new(USAGE)?;
+    let args: Args = docopt.parse()?.deserialize()?;
+    let toml_config: TomlConfig = match args.flag_config {
+        None => Default::default(),
+        Some(ref fname) => {
+            let path: &Path = Path::new(&fname[..]);
+            misc::load_toml::<TomlConfig>(path)?
+        }
+    };
+
+    let mut config = toml_config.analysis.clone().unwrap_or_default();
+    if let Some(tolerance) = args.flag_tolerance {
+        config.tolerance = tolerance;
+    }
+    if let Some(bins) = args.flag_bins {
+        config.bins = bins;
+    }
+    if args.flag_rmax.is_some() {
+        config.r_max = args.flag_rmax;
+    }
+    let mut sim_box = match args.flag_box {
+        Some(ref size) => Some(SimBox {
+            size: parse_box(size)?,
+            periodic: false,
+        }),
+        None => toml_config.sim_box,
+    };
+    if args.flag_periodic {
+        sim_box = match sim_box {
+            Some(b) => Some(SimBox {
+                periodic: true,
+                ..b
+            }),
+            None => return Err("--periodic needs a box, from --box or the config".into()),
+        };
+    }
+
+    let path: &Path = Path::new(&args.arg_particlefile);
+    let frames: Vec<Frame> = misc::deserialize_by_ext(path)?;
+    let start = args.flag_start.unwrap_or(toml_config.start);
+    let end = args
+        .flag_end
+        .or(toml_config.end)
+        .unwrap_or(frames.len())
+        .min(frames.len());
+    let stride = args.flag_stride.unwrap_or(toml_config.stride).max(1);
+    let indices: Vec<usize> = (start..end).step_by(stride).collect();
+    if indices.is_empty() {
+        let msg = format!(
+            "No frames to analyze from {}..{}; there are {}",
+            start,
+            end,
+            frames.len()
+        );
+        return Err(msg.into());
+    }
+
+    let mut out: Box<dyn Write> = match args.arg_csvfile {
+        Some(ref fname) => Box::new(io::BufWriter::new(File::create(fname)?)),
+        None => Box::new(io::stdout()),
+    };
+
+    if args.flag_rdf {
+        let sim_box = sim_box.ok_or("g(r) needs a box, from --box or the config")?;
+        let r_max = config.r_max(&sim_box);
+        let mut total = vec![0.; config.bins];
+        let mut rs = vec![];
+        for &index in &indices {
+            let g = analysis::radial_distribution(&frames[index], &sim_box, config.bins, r_max);
+            rs = g.iter().map(|&(r, _)| r).collect();
+            for (sum, (_, g)) in total.iter_mut().zip(g) {
+                *sum += g;
+            }
+        }
+        writeln!(out, "r,g")?;
+        for (r, sum) in rs.iter().zip(total) {
+            writeln!(out, "{},{}", r, sum / indices.len() as f32)?;
+        }
+    } else if args.flag_particles {
+        writeln!(out, "frame,names,coordination")?;
+        for &index in &indices {
+            let counts = analysis::coordination(&frames[index], config.tolerance, sim_box.as_ref());
+            for (ObjectID(names), n) in counts {
+                writeln!(out, "{},{},{}", index, csv_field(&names.join(" ")), n)?;
+            }
+        }
+    } else {
+        let values: Vec<_> = indices
+            .iter()
+            .map(|&index| config.values(&frames[index], sim_box.as_ref()))
+            .collect();
+        // Frames without spherocylinders have no nematic order, so some columns may be empty
+        let names: BTreeSet<&String> = values.iter().flat_map(|v| v.keys()).collect();
+        let header: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        writeln!(out, "frame,time,step,{}", header.join(","))?;
+        for (&index, values) in indices.iter().zip(&values) {
+            let frame = &frames[index];
+            let fields: Vec<String> = names.iter().map(|&n| optional(values.get(n))).collect();
+            writeln!(
+                out,
+                "{},{},{},{}",
+                index,
+                optional(frame.time),
+                optional(frame.step),
+                fields.join(",")
+            )?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// The main entry point.
+pub fn main() {
+    if let Err(err) = run() {
+        misc::exit_with_error(&*err);
+    }
+}
@@ -0,0 +1,96 @@
+//! # ParView
+//!
+#![deny(non_camel_case_types)]
+#![deny(unused_parens)]
+#![deny(non_upper_case_globals)]
+#![deny(unused_qualifications)]
+#![deny(missing_docs)]
+#![deny(unused_results)]
+
+extern crate docopt;
+extern crate serde;
+
+extern crate parview;
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use parview::{misc, Config, Frame, Palette, SvgExport, TomlConfig};
+
+// Write the Docopt usage string.
+const USAGE: &str = "
+Usage: pvexport [options] [--] <particlefile> <svgfile>
+
+Save a frame as an SVG, without opening a window.
+
+Options:
+    -h, --help              Help and usage
+    -p, --palette FILE      Use palette file (toml file), instead of default.
+    -c, --config FILE       Use config file (toml file), instead of default.
+    -f, --frame N           Frame to export [default: 0]
+
+
+Arguments:
+    <file>      json file representing the frames. json.gz also accepted, if
+                the extension is \".gz\".
+";
+
+#[derive(Deserialize)]
+struct Args {
+    flag_palette: Option<String>,
+    flag_config: Option<String>,
+    flag_frame: usize,
+    arg_particlefile: String,
+    arg_svgfile: String,
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let docopt = docopt::Docopt::new(USAGE)?;
+    let args: Args = docopt.parse()?.deserialize()?;
+    let toml_config: TomlConfig = match args.flag_config {
+        None => Default::default(),
+        Some(ref fname) => {
+            let path: &Path = Path::new(&fname[..]);
+            misc::load_toml::<TomlConfig>(path)?
+        }
+    };
+    let config: Config = toml_config.to_parviewer_config();
+
+    let path: &Path = Path::new(&args.arg_particlefile);
+    let frames: Vec<Frame> = misc::deserialize_by_ext(path)?;
+    let mut palette: Palette = match args.flag_palette {
+        None => Default::default(),
+        Some(fname) => {
+            let palette_path: &Path = Path::new(&fname[..]);
+            misc::load_toml::<Palette>(palette_path)?
+        }
+    };
+
+    let index = args.flag_frame;
+    let frame = frames.get(index).ok_or_else(|| {
+        format!(
+            "Frame {} is out of range; there are {}",
+            index,
+            frames.len()
+        )
+    })?;
+
+    // Place the camera as the viewer would at this frame
+    let mut export = SvgExport::new(&config);
+    export.camera = config.camera_at(index, frame);
+    if config.snapshot.transparent {
+        export.background = None;
+    }
+
+    export.save(frame, &mut palette, Path::new(&args.arg_svgfile))?;
+    println!("Saved SVG to {}", args.arg_svgfile);
+    Ok(())
+}
+
+/// The main entry point.
+pub fn main() {
+    if let Err(err) = run() {
+        misc::exit_with_error(&*err);
+    }
+}
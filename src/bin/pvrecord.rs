@@ -9,7 +9,6 @@
 
 extern crate docopt;
 extern crate kiss3d;
-extern crate mpeg_encoder;
 extern crate serde;
 
 extern crate parview;
@@ -17,31 +16,205 @@ extern crate parview;
 use std::f32::consts::PI;
 use std::path::Path;
 
-use kiss3d::event::{Action, Key, WindowEvent};
-use mpeg_encoder::Encoder;
+use kiss3d::event::{Action, Key, Modifiers, WindowEvent};
 use serde::Deserialize;
 
-use parview::{misc, Color, Config, Frame, Palette, Parviewer, TomlConfig, EPSILON};
+use parview::{
+    misc, Config, EventResult, Frame, Hook, Palette, Parviewer, Progress, TextStyle, TomlConfig,
+    VideoEncoder, VideoFormat, EPSILON,
+};
+
+/// Records each frame to the movie, and keeps key presses and mouse movements from
+/// changing the video.
+struct Recorder {
+    encoder: Box<dyn VideoEncoder>,
+    /// The first error from the encoder, which stops the recording
+    error: Option<String>,
+    lastix: usize,
+    rotate: f32,
+    framerate: f32,
+    text_style: TextStyle,
+    /// For frame-exact recording, the position of the timer for each video frame
+    schedule: Option<Vec<f32>>,
+    /// The next entry of `schedule` to show
+    next: usize,
+    /// Whether the window shows an entry of `schedule` that has not been added yet
+    drawn: bool,
+    /// Frames in the movie so far, including any skipped when resuming
+    frames: usize,
+    progress: Progress,
+}
+
+impl Recorder {
+    /// Add the image in the window to the movie. On an error, stop recording.
+    fn add_frame(&mut self, viewer: &mut Parviewer) {
+        let image = viewer.window.snap_image();
+        match self.encoder.add_frame(&image) {
+            Ok(()) => {
+                self.frames += 1;
+                self.progress.set(self.frames);
+            }
+            Err(err) => {
+                self.error = Some(err.to_string());
+                viewer.window.close();
+            }
+        }
+    }
+
+    /// Turn the camera by `rotate` for each of `frames` frames
+    fn rotate_camera(&self, viewer: &mut Parviewer, frames: usize) {
+        if self.rotate.abs() > EPSILON {
+            let new_yaw = viewer.camera.yaw() + (self.rotate * frames as f32 * PI / 180.);
+            viewer.camera.set_yaw(new_yaw);
+        }
+    }
+}
+
+impl Hook for Recorder {
+    fn pre_frame(&mut self, viewer: &mut Parviewer) {
+        let total = match self.schedule {
+            None => return,
+            Some(ref schedule) => schedule.len(),
+        };
+        // The window has just been drawn at the position set on the last tick
+        if self.drawn {
+            self.add_frame(viewer);
+            viewer
+                .window
+                .set_title(&format!("Parviewer ({} / {})", self.frames, total));
+        }
+        let position = self
+            .schedule
+            .as_ref()
+            .and_then(|schedule| schedule.get(self.next).cloned());
+        match position {
+            None => viewer.window.close(),
+            Some(t) => {
+                if self.drawn {
+                    self.rotate_camera(viewer, 1);
+                }
+                viewer.timer.t = t;
+                self.next += 1;
+                self.drawn = true;
+            }
+        }
+    }
+
+    fn on_key(
+        &mut self,
+        viewer: &mut Parviewer,
+        key: Key,
+        action: Action,
+        _modifiers: Modifiers,
+    ) -> EventResult {
+        if let (Key::Q, Action::Release) = (key, action) {
+            viewer.window.close();
+        }
+        // ignore all other keys
+        EventResult::Consume
+    }
+
+    fn on_mouse(&mut self, _viewer: &mut Parviewer, _event: &WindowEvent) -> EventResult {
+        // ignore drag and scroll events
+        EventResult::Consume
+    }
+
+    fn post_frame(&mut self, viewer: &mut Parviewer) {
+        let (x, y) = self.text_style.position;
+        if self.schedule.is_some() {
+            // Frames are added in `pre_frame`, once they have been drawn
+            viewer.draw_frame_text(x, y, self.text_style.color);
+            return;
+        }
+
+        self.rotate_camera(viewer, 1);
+        let ix = viewer.timer.get_index();
+        if ix < self.lastix {
+            viewer.window.close();
+            return;
+        };
+
+        self.lastix = ix;
+
+        viewer.draw_frame_text(x, y, self.text_style.color);
+        self.add_frame(viewer);
+        if self.error.is_some() {
+            return;
+        }
+
+        let frames_per_tick = viewer.timer.get_dt() / self.framerate;
+        let total = viewer
+            .timer
+            .total_loop_time()
+            .map(|n| format!("{}", (n / frames_per_tick + 0.5) as usize))
+            .unwrap_or_else(|| "?".into());
+
+        let title = format!(
+            "Parviewer ({} / {})",
+            ((viewer.timer.get_time() / frames_per_tick) + 0.5) as usize,
+            total
+        );
+        viewer.window.set_title(&title);
+        // println!("{}", title);
+    }
+}
 
 // Write the Docopt usage string.
 const USAGE: &str = "
-Usage: pvrecord [options] [--] <particlefile> <moviefile>
+Usage: pvrecord [options] [--compare FILE]... [--] <particlefile> <moviefile>
+
+The format is chosen by the extension of <moviefile>, unless given by --format or the
+config's [video] section:
+    .gif        An animated GIF.
+    .apng       An animated PNG.
+    .png        A PNG file per frame. <moviefile> may be a file name template, filled in
+                like `text_format`, e.g. \"frames/{index:05}.png\".
+    otherwise   Encoded by ffmpeg, e.g. .mp4, .mkv or .webm.
 
 Options:
     -h, --help              Help and usage
     -p, --palette FILE      Use palette file (toml file), instead of default.
     -c, --config FILE       Use config file (toml file), instead of default.
+    -f, --format FMT        Video format: ffmpeg, png, gif, apng, or mpeg (if built
+                            with the mpeg feature).
+    --exact                 Record a fixed number of video frames for each frame of
+                            the trajectory, instead of following the playback clock.
+                            Implied by the options below.
+    --hold N                Video frames for each frame of the trajectory.
+    --inbetween N           Video frames between each frame and the next, with the
+                            particles moved part of the way between them.
+    --end-hold N            Extra video frames of the last frame.
+    --resume                Continue an interrupted recording of a PNG sequence,
+                            skipping the files already written. Implies --exact.
+    --start N               First frame to record, instead of the config's start.
+    --end N                 End of the range (exclusive), instead of the config's end.
+    --stride N              Record every N-th frame, instead of the config's stride.
+    --compare FILE          Record another trajectory beside the first, as laid out
+                            in the config's [viewports] section. May be repeated.
+    --reference FILE        Color particles by how far they are from where they
+                            are in the same frame of FILE.
 
 
 Arguments:
-    <file>      json file representing the frames. json.gz also accepted, if
-                the extension is \".gz\".
+    <particlefile>  json file representing the frames. json.gz also accepted, if
+                    the extension is \".gz\".
 ";
 
 #[derive(Deserialize)]
 struct Args {
     flag_palette: Option<String>,
     flag_config: Option<String>,
+    flag_format: Option<String>,
+    flag_exact: bool,
+    flag_hold: Option<usize>,
+    flag_inbetween: Option<usize>,
+    flag_end_hold: Option<usize>,
+    flag_resume: bool,
+    flag_start: Option<usize>,
+    flag_end: Option<usize>,
+    flag_stride: Option<usize>,
+    flag_compare: Vec<String>,
+    flag_reference: Option<String>,
     arg_particlefile: String,
     arg_moviefile: String,
 }
@@ -49,7 +222,7 @@ struct Args {
 fn run() -> Result<(), Box<dyn std::error::Error>> {
     let docopt = docopt::Docopt::new(USAGE)?;
     let args: Args = docopt.parse()?.deserialize()?;
-    let toml_config: TomlConfig = match args.flag_config {
+    let mut toml_config: TomlConfig = match args.flag_config {
         None => Default::default(),
         Some(ref fname) => {
             let path: &Path = Path::new(&fname[..]);
@@ -58,6 +231,27 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let framerate = toml_config.framerate;
+    let format: Option<VideoFormat> = match args.flag_format {
+        None => None,
+        Some(ref name) => Some(name.parse()?),
+    };
+    toml_config.start = args.flag_start.unwrap_or(toml_config.start);
+    toml_config.end = args.flag_end.or(toml_config.end);
+    toml_config.stride = args.flag_stride.unwrap_or(toml_config.stride);
+    let mut record = toml_config.record.clone();
+    record.exact |= args.flag_exact || args.flag_resume;
+    if let Some(hold) = args.flag_hold {
+        record.hold = hold;
+        record.exact = true;
+    }
+    if let Some(inbetween) = args.flag_inbetween {
+        record.inbetween = inbetween;
+        record.exact = true;
+    }
+    if let Some(end_hold) = args.flag_end_hold {
+        record.end_hold = end_hold;
+        record.exact = true;
+    }
     let config: Config = toml_config.to_parviewer_config();
 
     let fname: &str = &args.arg_particlefile;
@@ -66,7 +260,7 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
     let frames: Vec<Frame> = misc::deserialize_by_ext(path)?;
     let palette: Palette = match args.flag_palette {
         None => Default::default(),
-        Some(fname) => {
+        Some(ref fname) => {
             let palette_path: &Path = Path::new(&fname[..]);
             misc::load_toml::<Palette>(palette_path)?
         }
@@ -74,25 +268,72 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     // println!("config: {:?}", config);
 
-    let mut viewer = Parviewer::new(frames, palette, config)?;
+    let first_palette = toml_config.viewports.palette(0, &palette)?;
+    let mut viewer = Parviewer::new(frames, first_palette, config)?;
+    viewer.label = misc::file_label(fname);
+    for (i, fname) in args.flag_compare.iter().enumerate() {
+        let frames: Vec<Frame> = misc::deserialize_by_ext(Path::new(fname))?;
+        let palette = toml_config.viewports.palette(i + 1, &palette)?;
+        viewer.add_trajectory(&misc::file_label(fname), frames, palette)?;
+    }
+    if let Some(ref fname) = args.flag_reference {
+        viewer.set_reference(misc::deserialize_by_ext(Path::new(fname))?)?;
+    }
     let _ = viewer.timer.at_least(toml_config.fps);
     // Record as fast as possible
     viewer.window.set_framerate_limit(Some(framerate as u64));
-    let text_color = Color(255, 255, 255);
-    let width = viewer.window.width();
-    let height = viewer.window.height();
-    let mut buf: Vec<u8> = Vec::with_capacity((width * height) as usize);
-
-    let mut encoder = Encoder::new_with_params(
-        &args.arg_moviefile,
-        viewer.window.width() as usize,
-        viewer.window.height() as usize,
-        None,                          // bit_rate
-        Some((1, framerate as usize)), // time base
-        None,
-        None,
-        None,
+    let text_style = viewer.get_config().text.clone();
+    let schedule = if record.exact {
+        let steps = viewer.timer.steps().unwrap_or(1);
+        let schedule = record.schedule(steps);
+        println!(
+            "Recording {} video frames of {} trajectory frames",
+            schedule.len(),
+            steps
+        );
+        // The recorder moves the timer itself, as fast as frames can be drawn
+        viewer.paused = true;
+        viewer.interpolate |= record.inbetween > 0;
+        viewer.window.set_framerate_limit(None);
+        Some(schedule)
+    } else {
+        None
+    };
+    let mut encoder = toml_config
+        .video
+        .open(Path::new(&args.arg_moviefile), framerate, format)?;
+    // Everything that changes the frames, so a resumed recording continues the same one
+    toml_config.record = record;
+    let settings = format!(
+        "size = {}x{}\nparticles = {:?}\ncompare = {:?}\nreference = {:?}\npalette = {:?}\n\
+         config = {:#?}\n",
+        viewer.window.width(),
+        viewer.window.height(),
+        args.arg_particlefile,
+        args.flag_compare,
+        args.flag_reference,
+        args.flag_palette,
+        toml_config
     );
+    encoder.set_settings(&settings);
+    let skip = if args.flag_resume {
+        encoder.resume()?
+    } else {
+        0
+    };
+    let total = match schedule {
+        Some(ref schedule) => Some(schedule.len()),
+        None => {
+            let frames_per_tick = viewer.timer.get_dt() / framerate;
+            viewer
+                .timer
+                .total_loop_time()
+                .map(|n| (n / frames_per_tick + 0.5) as usize)
+        }
+    };
+    if skip > 0 {
+        println!("Resuming after {} frames", skip);
+    }
 
     println!(
         "Sizes: {}, {}",
@@ -100,74 +341,34 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
         viewer.window.height() as usize
     );
 
-    let mut lastix = 0;
-
-    viewer.run(|viewer, _| {
-        if toml_config.rotate.abs() > EPSILON {
-            let new_yaw = viewer.camera.yaw() + (toml_config.rotate * PI / 180.);
-            viewer.camera.set_yaw(new_yaw);
-        }
-
-        let ix = viewer.timer.get_index();
-        if ix < lastix {
-            viewer.window.close();
-            return;
-        };
-
-        lastix = ix;
-
-        for mut event in viewer.window.events().iter() {
-            match event.value {
-                WindowEvent::Key(key, Action::Release, _) => {
-                    // Default to inhibiting, although this can be overridden
-                    let inhibit = true;
-                    if let Key::Q = key {
-                        viewer.window.close();
-                        return;
-                    }
-                    // ignore all other keys
-                    event.inhibited = inhibit;
-                }
-                WindowEvent::CursorPos(_, _, _) => {
-                    // ignore drag events
-                    event.inhibited = true;
-                }
-                _ => {}
-            }
-        }
-
-        viewer.draw_frame_text(0., 0., text_color);
-        viewer.window.snap(&mut buf);
-        encoder.encode_rgb(
-            viewer.window.width() as usize,
-            viewer.window.height() as usize,
-            &buf,
-            false,
-        );
-
-        let frames_per_tick = viewer.timer.get_dt() / framerate;
-        let total = viewer
-            .timer
-            .total_loop_time()
-            .map(|n| format!("{}", (n / frames_per_tick + 0.5) as usize))
-            .unwrap_or_else(|| "?".into());
-
-        let title = format!(
-            "Parviewer ({} / {})",
-            ((viewer.timer.get_time() / frames_per_tick) + 0.5) as usize,
-            total
-        );
-        viewer.window.set_title(&title);
-        // println!("{}", title);
-    });
+    let mut recorder = Recorder {
+        encoder,
+        error: None,
+        lastix: 0,
+        rotate: toml_config.rotate,
+        framerate,
+        text_style,
+        schedule,
+        next: skip,
+        drawn: false,
+        frames: skip,
+        progress: Progress::new(skip, total),
+    };
+    recorder.rotate_camera(&mut viewer, skip);
+    let hook: &mut dyn Hook = &mut recorder;
+    viewer.run_with_hooks(&mut [hook]);
+    recorder.progress.finish();
+    if let Some(err) = recorder.error.take() {
+        return Err(err.into());
+    }
+    recorder.encoder.finish()?;
+    println!("Saved {}", args.arg_moviefile);
     Ok(())
 }
 
 /// The main entry point.
 pub fn main() {
     if let Err(err) = run() {
-        println!("ERROR.");
-
-        misc::err_print(&*err);
+        misc::exit_with_error(&*err);
     }
 }
@@ -0,0 +1,146 @@
+//! # ParView
+//!
+#![deny(non_camel_case_types)]
+#![deny(unused_parens)]
+#![deny(non_upper_case_globals)]
+#![deny(unused_qualifications)]
+#![deny(missing_docs)]
+#![deny(unused_results)]
+
+extern crate docopt;
+extern crate serde;
+
+extern crate parview;
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use parview::misc::TemplateValue;
+use parview::{misc, Config, Frame, GltfExport, Palette, PovExport, TomlConfig};
+
+// Write the Docopt usage string.
+const USAGE: &str = "
+Usage: pvscene [options] [--] <particlefile> <output>
+
+Export a trajectory for offline rendering, without opening a window.
+
+The format is chosen by the extension of <output>:
+    .pov        A POV-Ray scene per frame. <output> is a file name template, filled
+                in like `text_format`, e.g. \"scenes/frame{index:04}.pov\".
+    .gltf/.glb  A single glTF 2.0 file, animated across the frames.
+
+Options:
+    -h, --help              Help and usage
+    -p, --palette FILE      Use palette file (toml file), instead of default.
+    -c, --config FILE       Use config file (toml file), instead of default.
+    --start N               First frame to export, instead of the config's start.
+    --end N                 End of the range (exclusive), instead of the config's end.
+    --stride N              Export every N-th frame, instead of the config's stride.
+    --fps FPS               Frames per second of a glTF animation, instead of the
+                            config's fps.
+
+
+Arguments:
+    <file>      json file representing the frames. json.gz also accepted, if
+                the extension is \".gz\".
+";
+
+#[derive(Deserialize)]
+struct Args {
+    flag_palette: Option<String>,
+    flag_config: Option<String>,
+    flag_start: Option<usize>,
+    flag_end: Option<usize>,
+    flag_stride: Option<usize>,
+    flag_fps: Option<f32>,
+    arg_particlefile: String,
+    arg_output: String,
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let docopt = docopt::Docopt::new(USAGE)?;
+    let args: Args = docopt.parse()?.deserialize()?;
+    let toml_config: TomlConfig = match args.flag_config {
+        None => Default::default(),
+        Some(ref fname) => {
+            let path: &Path = Path::new(&fname[..]);
+            misc::load_toml::<TomlConfig>(path)?
+        }
+    };
+    let fps = args.flag_fps.unwrap_or(toml_config.fps);
+    let config: Config = toml_config.to_parviewer_config();
+
+    let path: &Path = Path::new(&args.arg_particlefile);
+    let frames: Vec<Frame> = misc::deserialize_by_ext(path)?;
+    let mut palette: Palette = match args.flag_palette {
+        None => Default::default(),
+        Some(fname) => {
+            let palette_path: &Path = Path::new(&fname[..]);
+            misc::load_toml::<Palette>(palette_path)?
+        }
+    };
+
+    let start = args.flag_start.unwrap_or(config.start);
+    let end = args
+        .flag_end
+        .or(config.end)
+        .unwrap_or(frames.len())
+        .min(frames.len());
+    let stride = args.flag_stride.unwrap_or(config.stride).max(1);
+    let indices: Vec<usize> = (start..end).step_by(stride).collect();
+    if indices.is_empty() {
+        let msg = format!(
+            "No frames to export from {}..{}; there are {}",
+            start,
+            end,
+            frames.len()
+        );
+        return Err(msg.into());
+    }
+
+    let output = &args.arg_output;
+    match Path::new(output).extension().and_then(|s| s.to_str()) {
+        Some("pov") => {
+            if indices.len() > 1 && !output.contains('{') {
+                let msg = format!(
+                    "Exporting {} frames needs a file name template, e.g. \"frame{{index:04}}.pov\"",
+                    indices.len()
+                );
+                return Err(msg.into());
+            }
+            let mut export = PovExport::new(&config);
+            for &index in &indices {
+                let frame = &frames[index];
+                export.camera = config.camera_at(index, frame);
+                let filename = misc::format_template(output, |name| match name {
+                    "index" => Some(TemplateValue::Number(index as f64)),
+                    _ => frame.lookup(name),
+                });
+                export.save(frame, &mut palette, Path::new(&filename))?;
+                println!("Saved {}", filename);
+            }
+        }
+        Some("gltf") | Some("glb") => {
+            let export = GltfExport::new(&config, fps);
+            let scenes: Vec<_> = indices
+                .iter()
+                .map(|&index| (config.camera_at(index, &frames[index]), &frames[index]))
+                .collect();
+            export.save(&scenes, &mut palette, Path::new(output))?;
+            println!("Saved {} frames to {}", scenes.len(), output);
+        }
+        _ => {
+            let msg = format!("Unknown format for '{}'; use .pov, .gltf or .glb", output);
+            return Err(msg.into());
+        }
+    }
+    Ok(())
+}
+
+/// The main entry point.
+pub fn main() {
+    if let Err(err) = run() {
+        misc::exit_with_error(&*err);
+    }
+}
@@ -0,0 +1,739 @@
+//! Camera positions, projections, and scripted camera paths for movies.
+
+use na;
+
+use kiss3d::camera::{ArcBall, Camera};
+use kiss3d::context::Context;
+use kiss3d::event::{Action, MouseButton, WindowEvent};
+use kiss3d::resource::ShaderUniform;
+use kiss3d::window::Canvas;
+use serde::{Deserialize, Serialize};
+
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::f32::consts::PI;
+
+use objects::EPSILON;
+use viewports::{Rect, Viewport};
+
+#[cfg(test)]
+use toml;
+
+/// Near clipping plane of the perspective projection
+pub const ZNEAR: f32 = 0.1;
+/// Far clipping plane
+pub const ZFAR: f32 = 1024.0;
+
+/// A camera position. Angles are in degrees, as in `Config`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct CameraState {
+    /// Yaw of the camera, in degrees
+    pub yaw: f32,
+    /// Pitch of the camera, in degrees
+    pub pitch: f32,
+    /// Distance of the camera from the target
+    pub distance: f32,
+    /// Field-of-view range of the camera, in degrees
+    pub fov: f32,
+    /// The point the camera looks at
+    pub target: (f32, f32, f32),
+}
+
+impl CameraState {
+    /// The point the camera looks at
+    pub fn at(&self) -> na::Point3<f32> {
+        let (x, y, z) = self.target;
+        na::Point3::new(x, y, z)
+    }
+
+    /// The camera position, as `ArcBall` places it
+    pub fn eye(&self) -> na::Point3<f32> {
+        let yaw = self.yaw * PI / 180.;
+        // ArcBall keeps the pitch away from the poles
+        let pitch = (self.pitch * PI / 180.).clamp(0.01, PI - 0.01);
+        let offset = na::Vector3::new(
+            yaw.cos() * pitch.sin(),
+            pitch.cos(),
+            yaw.sin() * pitch.sin(),
+        );
+        self.at() + offset * self.distance
+    }
+
+    /// The transformation from world to camera coordinates, with the camera looking along -z
+    pub fn view_transform(&self) -> na::Isometry3<f32> {
+        na::Isometry3::look_at_rh(&self.eye(), &self.at(), &na::Vector3::y())
+    }
+}
+
+/// How the scene is projected onto the window
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "kebab-case")]
+pub enum Projection {
+    /// Distant objects look smaller, as set by the field of view
+    Perspective,
+    /// Parallel lines stay parallel, and objects keep their size at any distance
+    Orthographic,
+}
+
+/// A standard view direction, named by the side of the target the camera looks from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum View {
+    /// From the +x side, looking along -x
+    PlusX,
+    /// From the -x side, looking along +x
+    MinusX,
+    /// From the +y side (above), looking along -y
+    PlusY,
+    /// From the -y side (below), looking along +y
+    MinusY,
+    /// From the +z side, looking along -z
+    PlusZ,
+    /// From the -z side, looking along +z
+    MinusZ,
+    /// Along the (-1, -1, -1) diagonal, with all three axes equally foreshortened
+    Isometric,
+}
+
+impl View {
+    /// The (yaw, pitch) of the view, in degrees, as used by `ArcBall`
+    pub fn angles(self) -> (f32, f32) {
+        match self {
+            View::PlusX => (0., 90.),
+            View::MinusX => (180., 90.),
+            View::PlusY => (0., 0.),
+            View::MinusY => (0., 180.),
+            View::PlusZ => (90., 90.),
+            View::MinusZ => (-90., 90.),
+            View::Isometric => (45., (1. / 3f32.sqrt()).acos() * 180. / PI),
+        }
+    }
+
+    /// A short name, for the help overlay
+    pub fn name(self) -> &'static str {
+        match self {
+            View::PlusX => "+x",
+            View::MinusX => "-x",
+            View::PlusY => "+y",
+            View::MinusY => "-y",
+            View::PlusZ => "+z",
+            View::MinusZ => "-z",
+            View::Isometric => "isometric",
+        }
+    }
+}
+
+/// A camera position at a given frame. Fields that are not set are interpolated between the
+/// keyframes that do set them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+pub struct Keyframe {
+    /// The frame index. Fractional frames are allowed.
+    pub frame: f32,
+    /// Yaw of the camera, in degrees
+    pub yaw: Option<f32>,
+    /// Pitch of the camera, in degrees
+    pub pitch: Option<f32>,
+    /// Distance of the camera from the target
+    pub distance: Option<f32>,
+    /// Field-of-view range of the camera, in degrees
+    pub fov: Option<f32>,
+    /// The point the camera looks at
+    pub target: Option<(f32, f32, f32)>,
+}
+
+/// How to move between keyframes
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+    /// Constant speed between keyframes, with sudden changes at each keyframe
+    Linear,
+    /// Slow down into and speed up out of each keyframe
+    EaseInOut,
+    /// A smooth curve through all keyframes
+    CatmullRom,
+}
+
+/// A scripted camera path, as a series of keyframes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct CameraPath {
+    /// How to move between keyframes
+    pub interpolation: Interpolation,
+    /// The keyframes, in any order
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        CameraPath {
+            interpolation: Interpolation::CatmullRom,
+            keyframes: vec![],
+        }
+    }
+}
+
+impl CameraPath {
+    /// Returns true if there are no keyframes
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Interpolate the camera position at a (fractional) frame. Fields that no keyframe sets
+    /// are left unchanged.
+    pub fn apply(&self, frame: f32, state: &mut CameraState) {
+        if let Some(v) = self.interpolate(frame, |k| k.yaw) {
+            state.yaw = v;
+        }
+        if let Some(v) = self.interpolate(frame, |k| k.pitch) {
+            state.pitch = v;
+        }
+        if let Some(v) = self.interpolate(frame, |k| k.distance) {
+            state.distance = v;
+        }
+        if let Some(v) = self.interpolate(frame, |k| k.fov) {
+            state.fov = v;
+        }
+        let x = self.interpolate(frame, |k| k.target.map(|t| t.0));
+        let y = self.interpolate(frame, |k| k.target.map(|t| t.1));
+        let z = self.interpolate(frame, |k| k.target.map(|t| t.2));
+        if let (Some(x), Some(y), Some(z)) = (x, y, z) {
+            state.target = (x, y, z);
+        }
+    }
+
+    /// Interpolate a single field at a frame, using only the keyframes that set it.
+    fn interpolate<F>(&self, frame: f32, field: F) -> Option<f32>
+    where
+        F: Fn(&Keyframe) -> Option<f32>,
+    {
+        let mut points: Vec<(f32, f32)> = self
+            .keyframes
+            .iter()
+            .filter_map(|k| field(k).map(|v| (k.frame, v)))
+            .collect();
+        points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        let (first, last) = (*points.first()?, *points.last()?);
+        if frame <= first.0 {
+            return Some(first.1);
+        }
+        if frame >= last.0 {
+            return Some(last.1);
+        }
+
+        // points[i] is the last keyframe at or before `frame`
+        let i = points.iter().rposition(|p| p.0 <= frame)?;
+        let (p1, p2) = (points[i], points[i + 1]);
+        if p2.0 <= p1.0 {
+            return Some(p2.1);
+        }
+        let u = (frame - p1.0) / (p2.0 - p1.0);
+
+        Some(match self.interpolation {
+            Interpolation::Linear => p1.1 + u * (p2.1 - p1.1),
+            Interpolation::EaseInOut => {
+                let u = u * u * (3. - 2. * u);
+                p1.1 + u * (p2.1 - p1.1)
+            }
+            Interpolation::CatmullRom => {
+                let p0 = if i > 0 { points[i - 1].1 } else { p1.1 };
+                let p3 = points.get(i + 2).map(|p| p.1).unwrap_or(p2.1);
+                let (p1, p2) = (p1.1, p2.1);
+                0.5 * (2. * p1
+                    + (p2 - p0) * u
+                    + (2. * p0 - 5. * p1 + 4. * p2 - p3) * u * u
+                    + (3. * p1 - p0 - 3. * p2 + p3) * u * u * u)
+            }
+        })
+    }
+}
+
+/// One of `count` × `count` equal parts of the view, for rendering images larger than the
+/// window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    /// Number of tiles across (and down) the view
+    pub count: u32,
+    /// Column of this tile, from the left
+    pub column: u32,
+    /// Row of this tile, from the top
+    pub row: u32,
+}
+
+impl Tile {
+    /// The part of the view this tile covers
+    pub fn rect(self) -> Rect {
+        let n = self.count as f32;
+        Rect {
+            x: self.column as f32 / n,
+            y: self.row as f32 / n,
+            width: 1. / n,
+            height: 1. / n,
+        }
+    }
+
+    /// A matrix that stretches this tile of the projected view to fill the window
+    pub fn matrix(self) -> na::Matrix4<f32> {
+        // Undo shrinking the whole window into the tile
+        self.rect()
+            .matrix()
+            .try_inverse()
+            .unwrap_or_else(na::Matrix4::identity)
+    }
+}
+
+/// An `ArcBall` camera, with a choice of perspective or orthographic projection.
+///
+/// The mouse controls and camera position are handled by the `ArcBall`. In the orthographic
+/// projection, the width of the view scales with the camera distance, so zooming still works.
+///
+/// With viewports, the window is drawn in one pass per viewport, each showing only that
+/// viewport's objects, through its own camera or the main one.
+pub struct ViewCamera {
+    /// The orbiting camera, which handles the mouse and places the eye
+    pub arcball: ArcBall,
+    /// The current projection
+    pub projection: Projection,
+    /// Render only part of the view, stretched to fill the window
+    pub tile: Option<Tile>,
+    /// Parts of the window drawn separately. Empty draws the whole window at once.
+    pub viewports: Vec<Viewport>,
+    /// Width of the orthographic view, per unit of camera distance
+    ortho_scale: f32,
+    /// Width / height of the window
+    aspect: f32,
+    /// Field of view of the perspective projection, in radians. The `ArcBall` has no way to
+    /// change its own, so the perspective projection is built here.
+    fov: f32,
+    /// The viewport being drawn
+    pass: Cell<usize>,
+    /// Last known cursor position, in logical pixels
+    cursor: (f64, f64),
+    /// The viewport whose camera is being dragged
+    drag: Option<usize>,
+}
+
+impl ViewCamera {
+    /// Wrap an `ArcBall`. `extent` is the width of the orthographic view at the current camera
+    /// distance, and `fov` the field of view of the perspective projection, in radians.
+    pub fn new(arcball: ArcBall, projection: Projection, extent: f32, fov: f32) -> ViewCamera {
+        let ortho_scale = extent / arcball.dist().max(EPSILON);
+        ViewCamera {
+            arcball,
+            projection,
+            tile: None,
+            viewports: vec![],
+            ortho_scale,
+            aspect: 1.,
+            fov,
+            pass: Cell::new(0),
+            cursor: (0., 0.),
+            drag: None,
+        }
+    }
+
+    /// Switch between the perspective and orthographic projections
+    pub fn toggle_projection(&mut self) {
+        self.projection = match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        };
+    }
+
+    /// Set the field of view of the perspective projection, in radians
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+    }
+
+    /// Width of the orthographic view
+    pub fn extent(&self) -> f32 {
+        self.ortho_scale * self.arcball.dist()
+    }
+
+    /// The orthographic projection matrix. Nothing is clipped for being behind the eye.
+    fn orthographic(&self, arcball: &ArcBall) -> na::Matrix4<f32> {
+        let (_, zfar) = arcball.clip_planes();
+        let width = self.ortho_scale * arcball.dist();
+        let height = width / self.aspect;
+        na::Orthographic3::new(
+            -width / 2.,
+            width / 2.,
+            -height / 2.,
+            height / 2.,
+            -zfar,
+            zfar,
+        )
+        .to_homogeneous()
+    }
+
+    /// The projection matrix of a camera drawn in `rect`, including the tile if there is one.
+    /// In a viewport of another shape than the window, the view keeps its height.
+    fn projection_matrix(&self, arcball: &ArcBall, rect: Rect) -> na::Matrix4<f32> {
+        let mut projection = match self.projection {
+            Projection::Perspective => {
+                let (znear, zfar) = arcball.clip_planes();
+                na::Perspective3::new(self.aspect, self.fov, znear, zfar).to_homogeneous()
+            }
+            Projection::Orthographic => self.orthographic(arcball),
+        };
+        for j in 0..4 {
+            projection[(0, j)] *= rect.height / rect.width;
+        }
+        match self.tile {
+            None => projection,
+            Some(tile) => tile.matrix() * projection,
+        }
+    }
+
+    /// The camera of a viewport
+    fn viewport_arcball(&self, index: usize) -> &ArcBall {
+        self.viewports
+            .get(index)
+            .and_then(|v| v.arcball.as_ref())
+            .unwrap_or(&self.arcball)
+    }
+
+    /// The camera of a viewport, or the main camera for None
+    fn viewport_arcball_mut(&mut self, index: Option<usize>) -> &mut ArcBall {
+        match index
+            .and_then(|i| self.viewports.get_mut(i))
+            .and_then(|v| v.arcball.as_mut())
+        {
+            Some(arcball) => arcball,
+            None => &mut self.arcball,
+        }
+    }
+
+    /// Where a viewport is in the window
+    fn viewport_rect(&self, index: usize) -> Rect {
+        self.viewports.get(index).map_or(Rect::full(), |v| v.rect)
+    }
+
+    /// The transformation from the world to the normalized device coordinates of the whole
+    /// window, for the objects in a viewport
+    fn viewport_transformation(&self, index: usize) -> na::Matrix4<f32> {
+        let arcball = self.viewport_arcball(index);
+        let rect = self.viewport_rect(index);
+        rect.matrix()
+            * self.projection_matrix(arcball, rect)
+            * arcball.view_transform().to_homogeneous()
+    }
+
+    /// The viewport containing a point in the window frame (0 to 1, from the top left)
+    pub fn viewport_at(&self, x: f32, y: f32) -> Option<usize> {
+        self.viewports.iter().position(|v| v.rect.contains(x, y))
+    }
+
+    /// The ray through a point in the window frame (0 to 1, from the top left): the viewport
+    /// it is in, a point on the ray, and its direction.
+    pub fn ray(&self, x: f32, y: f32) -> Option<(usize, na::Point3<f32>, na::Vector3<f32>)> {
+        let index = if self.viewports.is_empty() {
+            0
+        } else {
+            self.viewport_at(x, y)?
+        };
+        let inverse = self.viewport_transformation(index).try_inverse()?;
+        let (nx, ny) = (2. * x - 1., 1. - 2. * y);
+        let near = na::Point3::from_homogeneous(inverse * na::Vector4::new(nx, ny, -1., 1.))?;
+        let far = na::Point3::from_homogeneous(inverse * na::Vector4::new(nx, ny, 1., 1.))?;
+        Some((index, near, (far - near).normalize()))
+    }
+
+    /// Yaw of the camera, in radians
+    pub fn yaw(&self) -> f32 {
+        self.arcball.yaw()
+    }
+
+    /// Set the yaw of the camera, in radians
+    pub fn set_yaw(&mut self, yaw: f32) {
+        self.arcball.set_yaw(yaw)
+    }
+
+    /// Pitch of the camera, in radians
+    pub fn pitch(&self) -> f32 {
+        self.arcball.pitch()
+    }
+
+    /// Set the pitch of the camera, in radians
+    pub fn set_pitch(&mut self, pitch: f32) {
+        self.arcball.set_pitch(pitch)
+    }
+
+    /// Distance from the camera to the target
+    pub fn dist(&self) -> f32 {
+        self.arcball.dist()
+    }
+
+    /// Set the distance from the camera to the target
+    pub fn set_dist(&mut self, dist: f32) {
+        self.arcball.set_dist(dist)
+    }
+
+    /// The point the camera looks at
+    pub fn at(&self) -> na::Point3<f32> {
+        self.arcball.at()
+    }
+
+    /// Set the point the camera looks at
+    pub fn set_at(&mut self, at: na::Point3<f32>) {
+        self.arcball.set_at(at)
+    }
+
+    /// Limit drawing to a part of the window
+    fn set_viewport(canvas: &Canvas, rect: Rect) {
+        let (width, height) = canvas.size();
+        let (x, y, w, h) = rect.pixels(width, height);
+        // GL counts from the bottom left
+        let y = height - y - h;
+        let ctxt = Context::get();
+        ctxt.viewport(x as i32, y as i32, w as i32, h as i32);
+        ctxt.scissor(x as i32, y as i32, w as i32, h as i32);
+    }
+
+    /// The parts of the window drawn in separate passes: those of the viewports, or else the
+    /// whole window
+    pub fn pass_rects(&self) -> Vec<Rect> {
+        (0..self.num_passes())
+            .map(|i| self.viewport_rect(i))
+            .collect()
+    }
+}
+
+impl Camera for ViewCamera {
+    fn handle_event(&mut self, canvas: &Canvas, event: &WindowEvent) {
+        if self.viewports.iter().all(|v| v.arcball.is_none()) {
+            return self.arcball.handle_event(canvas, event);
+        }
+        // Separate cameras: the mouse moves the camera of the viewport it is over
+        let (width, height) = canvas.size();
+        let hidpi = canvas.hidpi_factor();
+        let under_cursor = self.viewport_at(
+            (self.cursor.0 * hidpi / width.max(1) as f64) as f32,
+            (self.cursor.1 * hidpi / height.max(1) as f64) as f32,
+        );
+        let dragging = [MouseButton::Button1, MouseButton::Button2]
+            .iter()
+            .any(|&b| canvas.get_mouse_button(b) == Action::Press);
+        match *event {
+            WindowEvent::CursorPos(x, y, _) if !dragging => {
+                // Keep every camera up to date with the cursor, so none jumps when dragged
+                self.cursor = (x, y);
+                self.arcball.handle_event(canvas, event);
+                for viewport in &mut self.viewports {
+                    if let Some(ref mut arcball) = viewport.arcball {
+                        arcball.handle_event(canvas, event);
+                    }
+                }
+            }
+            WindowEvent::CursorPos(x, y, _) => {
+                self.cursor = (x, y);
+                let drag = self.drag;
+                self.viewport_arcball_mut(drag).handle_event(canvas, event);
+            }
+            WindowEvent::MouseButton(_, Action::Press, _) => {
+                self.drag = under_cursor;
+                self.viewport_arcball_mut(under_cursor)
+                    .handle_event(canvas, event);
+            }
+            WindowEvent::Scroll(..) => {
+                self.viewport_arcball_mut(under_cursor)
+                    .handle_event(canvas, event);
+            }
+            WindowEvent::FramebufferSize(..) => {
+                self.arcball.handle_event(canvas, event);
+                for viewport in &mut self.viewports {
+                    if let Some(ref mut arcball) = viewport.arcball {
+                        arcball.handle_event(canvas, event);
+                    }
+                }
+            }
+            // Keys move the main camera
+            _ => self.arcball.handle_event(canvas, event),
+        }
+    }
+
+    fn eye(&self) -> na::Point3<f32> {
+        self.viewport_arcball(self.pass.get()).eye()
+    }
+
+    fn view_transform(&self) -> na::Isometry3<f32> {
+        self.viewport_arcball(self.pass.get()).view_transform()
+    }
+
+    fn transformation(&self) -> na::Matrix4<f32> {
+        // As seen in the whole window, for projecting and picking
+        self.viewport_transformation(0)
+    }
+
+    fn inverse_transformation(&self) -> na::Matrix4<f32> {
+        self.transformation()
+            .try_inverse()
+            .unwrap_or_else(na::Matrix4::identity)
+    }
+
+    fn clip_planes(&self) -> (f32, f32) {
+        self.arcball.clip_planes()
+    }
+
+    fn update(&mut self, canvas: &Canvas) {
+        let (width, height) = canvas.size();
+        if width > 0 && height > 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+        self.arcball.update(canvas);
+        for viewport in &mut self.viewports {
+            if let Some(ref mut arcball) = viewport.arcball {
+                arcball.update(canvas);
+            }
+        }
+    }
+
+    fn upload(
+        &self,
+        pass: usize,
+        proj: &mut ShaderUniform<na::Matrix4<f32>>,
+        view: &mut ShaderUniform<na::Matrix4<f32>>,
+    ) {
+        let arcball = self.viewport_arcball(pass);
+        let rect = self.viewport_rect(pass);
+        proj.upload(&self.projection_matrix(arcball, rect));
+        view.upload(&arcball.view_transform().to_homogeneous());
+    }
+
+    fn num_passes(&self) -> usize {
+        self.viewports.len().max(1)
+    }
+
+    fn start_pass(&self, pass: usize, canvas: &Canvas) {
+        self.pass.set(pass);
+        if self.viewports.is_empty() {
+            return;
+        }
+        ViewCamera::set_viewport(canvas, self.viewport_rect(pass));
+        for (i, viewport) in self.viewports.iter().enumerate() {
+            // Nodes are shared handles, so this shows and hides the viewport's objects
+            let mut group = viewport.group.clone();
+            group.set_visible(i == pass);
+        }
+    }
+
+    fn render_complete(&self, canvas: &Canvas) {
+        self.pass.set(0);
+        if self.viewports.is_empty() {
+            return;
+        }
+        ViewCamera::set_viewport(canvas, Rect::full());
+        for viewport in &self.viewports {
+            let mut group = viewport.group.clone();
+            group.set_visible(true);
+        }
+    }
+}
+
+#[test]
+fn camera_path_interpolation() {
+    let s = "
+        interpolation = \"linear\"
+
+        [[keyframes]]
+        frame = 0
+        yaw = 0
+        distance = 2
+
+        [[keyframes]]
+        frame = 10
+        yaw = 90
+        target = [1, 0, 0]
+
+        [[keyframes]]
+        frame = 20
+        yaw = 360
+        distance = 4
+    ";
+    let mut path: CameraPath = toml::from_str(s).unwrap();
+    let mut state = CameraState {
+        yaw: 0.,
+        pitch: 45.,
+        distance: 1.,
+        fov: 30.,
+        target: (0., 0., 0.),
+    };
+
+    path.apply(5., &mut state);
+    assert_eq!(state.yaw, 45.);
+    assert_eq!(state.pitch, 45.);
+    assert_eq!(state.distance, 2.5);
+    assert_eq!(state.target, (1., 0., 0.));
+
+    path.apply(-1., &mut state);
+    assert_eq!(state.yaw, 0.);
+    path.apply(25., &mut state);
+    assert_eq!(state.yaw, 360.);
+    assert_eq!(state.distance, 4.);
+
+    // Smooth interpolations still pass through the keyframes
+    for &interpolation in &[Interpolation::EaseInOut, Interpolation::CatmullRom] {
+        path.interpolation = interpolation;
+        path.apply(10., &mut state);
+        assert_eq!(state.yaw, 90.);
+        path.apply(15., &mut state);
+        assert!(state.yaw > 90. && state.yaw < 360.);
+    }
+}
+
+#[test]
+fn camera_views() {
+    let eye = |view: View| {
+        // The eye position used by ArcBall
+        let (yaw, pitch) = view.angles();
+        let (yaw, pitch) = (yaw * PI / 180., pitch * PI / 180.);
+        let eye = (
+            yaw.cos() * pitch.sin(),
+            pitch.cos(),
+            yaw.sin() * pitch.sin(),
+        );
+        let round = |x: f32| (x * 1000.).round() / 1000.;
+        (round(eye.0), round(eye.1), round(eye.2))
+    };
+    assert_eq!(eye(View::PlusX), (1., 0., 0.));
+    assert_eq!(eye(View::MinusX), (-1., 0., 0.));
+    assert_eq!(eye(View::PlusY), (0., 1., 0.));
+    assert_eq!(eye(View::MinusY), (0., -1., 0.));
+    assert_eq!(eye(View::PlusZ), (0., 0., 1.));
+    assert_eq!(eye(View::MinusZ), (0., 0., -1.));
+    let (x, y, z) = eye(View::Isometric);
+    assert_eq!((x, y), (z, z));
+}
+
+#[test]
+fn camera_tiles() {
+    let corner = |tile: Tile, x: f32, y: f32| {
+        let p = tile.matrix() * na::Vector4::new(x, y, 0., 1.);
+        // Round away the error of inverting the matrix
+        ((p.x * 1e5).round() / 1e5, (p.y * 1e5).round() / 1e5)
+    };
+    // The top left quarter of the view fills the window
+    let top_left = Tile {
+        count: 2,
+        column: 0,
+        row: 0,
+    };
+    assert_eq!(corner(top_left, -1., 1.), (-1., 1.));
+    assert_eq!(corner(top_left, 0., 0.), (1., -1.));
+    // The middle of three
+    let middle = Tile {
+        count: 3,
+        column: 1,
+        row: 1,
+    };
+    assert_eq!(corner(middle, 0., 0.), (0., 0.));
+    assert_eq!(corner(middle, 1. / 3., -1. / 3.), (1., -1.));
+    // One tile is the whole view
+    let whole = Tile {
+        count: 1,
+        column: 0,
+        row: 0,
+    };
+    assert_eq!(corner(whole, -1., 1.), (-1., 1.));
+    assert_eq!(corner(whole, 1., -1.), (1., -1.));
+}
@@ -0,0 +1,365 @@
+//! Clusters of touching particles: groups connected by contacts, found with a union-find over
+//! the contacts of `contacts::find_contacts`.
+
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use contacts::find_contacts;
+use objects::{Frame, ObjectID, SimBox};
+use palette::{Color, DEFAULT_COLORS};
+
+#[cfg(test)]
+use objects::{Sphere, Spherocylinder};
+#[cfg(test)]
+use std::collections::BTreeMap;
+#[cfg(test)]
+use toml;
+
+/// Disjoint sets of indices, merged by size with path halving
+struct DisjointSets {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> DisjointSets {
+        DisjointSets {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        }
+    }
+
+    /// The index standing for the set that holds `i`
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Merge the sets holding `a` and `b`
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (small, large) = if self.size[a] < self.size[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[small] = large;
+        self.size[large] += self.size[small];
+    }
+}
+
+/// The clusters of a frame: particles connected by contacts, with gaps of up to `tolerance`.
+/// Each particle is in exactly one cluster, so one that touches nothing is a cluster of its
+/// own. Clusters are sorted largest first, and those of the same size in the order of the
+/// frame (spheres, then spherocylinders); each lists its particles in that order.
+pub fn find_clusters(
+    frame: &Frame,
+    tolerance: f32,
+    sim_box: Option<&SimBox>,
+) -> Vec<Vec<ObjectID>> {
+    let names: Vec<&ObjectID> = frame
+        .spheres
+        .iter()
+        .map(|s| &s.names)
+        .chain(frame.spherocylinders.iter().map(|s| &s.names))
+        .collect();
+    let indices: HashMap<&ObjectID, usize> =
+        names.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+
+    let mut sets = DisjointSets::new(names.len());
+    for contact in find_contacts(frame, tolerance, sim_box) {
+        let (ref a, ref b) = contact.between;
+        if let (Some(&i), Some(&j)) = (indices.get(a), indices.get(b)) {
+            sets.union(i, j);
+        }
+    }
+
+    let mut clusters: Vec<Vec<ObjectID>> = vec![];
+    let mut cluster_of: HashMap<usize, usize> = HashMap::new();
+    for (i, &name) in names.iter().enumerate() {
+        let root = sets.find(i);
+        let next = clusters.len();
+        let c = *cluster_of.entry(root).or_insert(next);
+        if c == next {
+            clusters.push(vec![]);
+        }
+        clusters[c].push(name.clone());
+    }
+    // A stable sort, so clusters of the same size stay in the order of the frame
+    clusters.sort_by_key(|c| Reverse(c.len()));
+    clusters
+}
+
+/// Statistics of the clusters of a frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterStats {
+    /// Number of clusters
+    pub count: usize,
+    /// Number of particles in the largest cluster
+    pub largest: usize,
+    /// Number of particles in all the clusters
+    pub particles: usize,
+}
+
+impl ClusterStats {
+    /// Statistics of some clusters
+    pub fn new(clusters: &[Vec<ObjectID>]) -> ClusterStats {
+        ClusterStats {
+            count: clusters.len(),
+            largest: clusters.iter().map(|c| c.len()).max().unwrap_or(0),
+            particles: clusters.iter().map(|c| c.len()).sum(),
+        }
+    }
+
+    /// A short description, for the status line
+    pub fn describe(&self) -> String {
+        let mean = if self.count > 0 {
+            self.particles as f32 / self.count as f32
+        } else {
+            0.
+        };
+        format!(
+            "{} clusters, largest {}, mean {:.1}",
+            self.count, self.largest, mean
+        )
+    }
+}
+
+/// How clusters are found and colored, when coloring by cluster (`ColorMode::Cluster`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct ClusterConfig {
+    /// Largest gap between two particles of the same cluster
+    pub tolerance: f32,
+    /// Smallest cluster shown; particles of smaller clusters are hidden
+    pub min_size: usize,
+    /// Colors of the clusters. A cluster keeps the color of the cluster of the last frame it
+    /// shares the most particles with; others take the colors in order, largest first, going
+    /// round them again when there are more clusters than colors.
+    pub colors: Vec<Color>,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        ClusterConfig {
+            tolerance: 0.,
+            min_size: 1,
+            // The palette's defaults, without white and black
+            colors: DEFAULT_COLORS[..9]
+                .iter()
+                .map(|&(r, g, b)| Color(r, g, b))
+                .collect(),
+        }
+    }
+}
+
+impl ClusterConfig {
+    /// The clusters of a frame that are shown: those of at least `min_size` particles
+    pub fn shown<'a>(&self, clusters: &'a [Vec<ObjectID>]) -> &'a [Vec<ObjectID>] {
+        // Clusters are sorted largest first
+        let n = clusters
+            .iter()
+            .take_while(|c| c.len() >= self.min_size)
+            .count();
+        &clusters[..n]
+    }
+
+    /// A copy of a frame with only the particles of some clusters
+    pub fn filter(&self, frame: &Frame, clusters: &[Vec<ObjectID>]) -> Frame {
+        let kept: HashSet<&ObjectID> = clusters.iter().flatten().collect();
+        Frame {
+            spheres: frame
+                .spheres
+                .iter()
+                .filter(|s| kept.contains(&s.names))
+                .cloned()
+                .collect(),
+            spherocylinders: frame
+                .spherocylinders
+                .iter()
+                .filter(|s| kept.contains(&s.names))
+                .cloned()
+                .collect(),
+            text: frame.text.clone(),
+            time: frame.time,
+            step: frame.step,
+            values: frame.values.clone(),
+            forces: frame.forces.clone(),
+        }
+    }
+}
+
+/// The colors of the clusters of a trajectory, kept from frame to frame
+#[derive(Debug, Clone, Default)]
+pub struct ClusterColors {
+    /// The index into `ClusterConfig::colors` of the cluster of each particle, in the last
+    /// frame colored
+    last: HashMap<ObjectID, usize>,
+}
+
+impl ClusterColors {
+    /// The color of each particle of some clusters, sorted largest first. Each cluster takes
+    /// the color of the cluster of the last frame it shares the most particles with, if no
+    /// larger cluster has taken it; otherwise the least used color, the first of them if
+    /// several are.
+    pub fn assign(
+        &mut self,
+        config: &ClusterConfig,
+        clusters: &[Vec<ObjectID>],
+    ) -> Vec<(ObjectID, Color)> {
+        if config.colors.is_empty() {
+            self.last.clear();
+            return vec![];
+        }
+        let mut uses = vec![0usize; config.colors.len()];
+        let mut assigned = Vec::with_capacity(clusters.len());
+        for cluster in clusters {
+            let mut shared = vec![0usize; uses.len()];
+            for name in cluster {
+                if let Some(&c) = self.last.get(name) {
+                    if c < shared.len() {
+                        shared[c] += 1;
+                    }
+                }
+            }
+            // The first of the most shared colors still free, if any are shared
+            let matched = (0..uses.len())
+                .filter(|&c| shared[c] > 0 && uses[c] == 0)
+                .max_by_key(|&c| (shared[c], Reverse(c)));
+            let least_used = || (0..uses.len()).min_by_key(|&c| (uses[c], c)).unwrap_or(0);
+            let c = matched.unwrap_or_else(least_used);
+            uses[c] += 1;
+            assigned.push(c);
+        }
+
+        self.last = clusters
+            .iter()
+            .zip(&assigned)
+            .flat_map(|(cluster, &c)| cluster.iter().map(move |name| (name.clone(), c)))
+            .collect();
+        clusters
+            .iter()
+            .zip(assigned)
+            .flat_map(|(cluster, c)| {
+                let color = config.colors[c];
+                cluster.iter().map(move |name| (name.clone(), color))
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn clusters() {
+    let names = |n: &str| ObjectID(vec![n.into()]);
+    let sphere = |n: &str, x: f32| Sphere {
+        loc: (x, 0., 0.),
+        diameter: 1.,
+        names: names(n),
+        vectors: BTreeMap::new(),
+    };
+    // A chain of three, a pair, and one alone
+    let frame = Frame {
+        spheres: vec![
+            sphere("lone", -10.),
+            sphere("a", 0.),
+            sphere("b", 1.),
+            sphere("c", 1.9),
+            sphere("d", 5.),
+            sphere("e", 5.95),
+        ],
+        spherocylinders: vec![],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![],
+    };
+    let clusters = find_clusters(&frame, 0., None);
+    let sizes: Vec<usize> = clusters.iter().map(|c| c.len()).collect();
+    assert_eq!(sizes, vec![3, 2, 1]);
+    assert_eq!(clusters[0], vec![names("a"), names("b"), names("c")]);
+    assert_eq!(clusters[2], vec![names("lone")]);
+
+    // With a larger tolerance, c reaches d
+    let sizes: Vec<usize> = find_clusters(&frame, 2.5, None)
+        .iter()
+        .map(|c| c.len())
+        .collect();
+    assert_eq!(sizes, vec![5, 1]);
+
+    // A rod touching the lone sphere makes a second pair, after the first in the frame
+    let bridged = Frame {
+        spherocylinders: vec![Spherocylinder {
+            loc: (-8., 0., 0.),
+            axis: (4., 0., 0.),
+            diameter: 1.,
+            names: names("rod"),
+            vectors: BTreeMap::new(),
+        }],
+        ..frame.clone()
+    };
+    let sizes: Vec<usize> = find_clusters(&bridged, 0., None)
+        .iter()
+        .map(|c| c.len())
+        .collect();
+    assert_eq!(sizes, vec![3, 2, 2]);
+
+    let config: ClusterConfig =
+        toml::from_str("min_size = 2\ncolors = [[255, 0, 0], [0, 0, 255]]").unwrap();
+    let shown = config.shown(&clusters);
+    assert_eq!(shown.len(), 2);
+    let stats = ClusterStats::new(shown);
+    assert_eq!(stats.largest, 3);
+    assert_eq!(stats.describe(), "2 clusters, largest 3, mean 2.5");
+
+    let mut cluster_colors = ClusterColors::default();
+    let colors = cluster_colors.assign(&config, shown);
+    assert_eq!(colors.len(), 5);
+    assert_eq!(colors[0], (names("a"), Color(255, 0, 0)));
+    assert_eq!(colors[4], (names("e"), Color(0, 0, 255)));
+
+    // When the pair grows larger than the chain, both keep their colors
+    let grown = vec![
+        vec![names("d"), names("e"), names("f"), names("g")],
+        vec![names("a"), names("b"), names("c")],
+    ];
+    let colors = cluster_colors.assign(&config, &grown);
+    assert_eq!(colors[0], (names("d"), Color(0, 0, 255)));
+    assert_eq!(colors[4], (names("a"), Color(255, 0, 0)));
+    // A new cluster takes the least used color, and one that shares its color with a larger
+    // cluster gives it up
+    let split = vec![
+        vec![names("d"), names("e"), names("f")],
+        vec![names("a"), names("b")],
+        vec![names("g"), names("h")],
+    ];
+    let colors: Vec<Color> = cluster_colors
+        .assign(&config, &split)
+        .into_iter()
+        .map(|(_, c)| c)
+        .collect();
+    assert_eq!(colors[0], Color(0, 0, 255));
+    assert_eq!(colors[3], Color(255, 0, 0));
+    assert_eq!(colors[5], Color(255, 0, 0));
+
+    let filtered = config.filter(&frame, shown);
+    let kept: Vec<&ObjectID> = filtered.spheres.iter().map(|s| &s.names).collect();
+    assert_eq!(
+        kept,
+        vec![
+            &names("a"),
+            &names("b"),
+            &names("c"),
+            &names("d"),
+            &names("e")
+        ]
+    );
+}
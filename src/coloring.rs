@@ -0,0 +1,238 @@
+//! Ways of coloring particles other than by name with the palette, such as by their number of
+//! contacts or the direction of their axes.
+
+use na;
+use serde::{Deserialize, Serialize};
+
+use analysis;
+use analysis::AnalysisConfig;
+use objects::{Frame, ObjectID, SimBox, EPSILON};
+use palette;
+use palette::Color;
+
+#[cfg(test)]
+use objects::{Sphere, Spherocylinder};
+#[cfg(test)]
+use std::collections::BTreeMap;
+#[cfg(test)]
+use toml;
+
+/// What particles are colored by
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    /// Their names, with the palette
+    Palette,
+    /// Their number of contacts
+    Coordination,
+    /// The direction of the axes of spherocylinders, as red, green and blue for x, y and z
+    Axis,
+    /// The angle between the axes of spherocylinders and the director
+    Director,
+    /// The cluster of touching particles they belong to, with the colors of `ClusterConfig`
+    Cluster,
+}
+
+impl ColorMode {
+    /// All the modes, in the order the G key goes through them
+    pub const ALL: [ColorMode; 5] = [
+        ColorMode::Palette,
+        ColorMode::Coordination,
+        ColorMode::Axis,
+        ColorMode::Director,
+        ColorMode::Cluster,
+    ];
+
+    /// The mode after this one, going back to the first after the last
+    pub fn next(self) -> ColorMode {
+        let i = ColorMode::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        ColorMode::ALL[(i + 1) % ColorMode::ALL.len()]
+    }
+
+    /// A short description, for printing
+    pub fn describe(self) -> &'static str {
+        match self {
+            ColorMode::Palette => "names",
+            ColorMode::Coordination => "number of contacts",
+            ColorMode::Axis => "axis direction",
+            ColorMode::Director => "angle to the director",
+            ColorMode::Cluster => "cluster",
+        }
+    }
+}
+
+/// How particles are colored, other than by the palette
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct ColoringConfig {
+    /// The mode when the viewer starts
+    pub mode: ColorMode,
+    /// Colors from 0 up to `max`, evenly spaced, for modes with a scale
+    pub colors: Vec<Color>,
+    /// Value at the top of the scale. None uses the largest in each frame.
+    pub max: Option<f32>,
+    /// Colors around the cyclic scale of angles to the director, from 0 to 180 degrees and back
+    /// to the first, evenly spaced
+    pub angle_colors: Vec<Color>,
+    /// The director to measure angles from. None uses the nematic director of each frame.
+    pub director: Option<(f32, f32, f32)>,
+}
+
+impl Default for ColoringConfig {
+    fn default() -> Self {
+        ColoringConfig {
+            mode: ColorMode::Palette,
+            colors: vec![
+                Color(68, 1, 84),
+                Color(59, 82, 139),
+                Color(33, 145, 140),
+                Color(94, 201, 98),
+                Color(253, 231, 37),
+            ],
+            max: None,
+            // Symmetric about the middle, so that angles just below 180 degrees look like those
+            // just above 0, as the sign of an axis has no meaning
+            angle_colors: vec![
+                Color(226, 217, 226),
+                Color(94, 111, 185),
+                Color(47, 20, 56),
+                Color(94, 111, 185),
+            ],
+            director: None,
+        }
+    }
+}
+
+impl ColoringConfig {
+    /// The director to measure angles from in a frame, of unit length: the configured one, or
+    /// else the nematic director. None if there is neither.
+    pub fn director(&self, frame: &Frame) -> Option<na::Vector3<f32>> {
+        match self.director {
+            Some((x, y, z)) => na::Vector3::new(x, y, z).try_normalize(EPSILON),
+            None => analysis::nematic_order(frame).map(|n| n.director),
+        }
+    }
+
+    /// The color of each particle of the frame in `mode`, or None to use the palette. The axis
+    /// and director modes color only spherocylinders, leaving spheres to the palette. The
+    /// cluster mode is colored by `ClusterColors` instead, so this gives None for it.
+    pub fn colors(
+        &self,
+        mode: ColorMode,
+        frame: &Frame,
+        analysis: &AnalysisConfig,
+        sim_box: Option<&SimBox>,
+    ) -> Option<Vec<(ObjectID, Color)>> {
+        let values: Vec<(ObjectID, f32)> = match mode {
+            ColorMode::Palette | ColorMode::Cluster => return None,
+            ColorMode::Coordination => analysis::coordination(frame, analysis.tolerance, sim_box)
+                .into_iter()
+                .map(|(id, n)| (id, n as f32))
+                .collect(),
+            ColorMode::Axis => {
+                let colors = frame
+                    .spherocylinders
+                    .iter()
+                    .filter_map(|s| {
+                        let u = s.get_axis().try_normalize(EPSILON)?;
+                        let channel = |c: f32| (c.abs() * 255.).round() as u8;
+                        Some((
+                            s.names.clone(),
+                            Color(channel(u.x), channel(u.y), channel(u.z)),
+                        ))
+                    })
+                    .collect();
+                return Some(colors);
+            }
+            ColorMode::Director => {
+                let director = match self.director(frame) {
+                    Some(d) => d,
+                    None => return Some(vec![]),
+                };
+                let colors = frame
+                    .spherocylinders
+                    .iter()
+                    .filter_map(|s| {
+                        let u = s.get_axis().try_normalize(EPSILON)?;
+                        let angle = u.dot(&director).clamp(-1., 1.).acos();
+                        let fraction = angle / std::f32::consts::PI;
+                        Some((
+                            s.names.clone(),
+                            palette::cyclic_colormap(&self.angle_colors, fraction),
+                        ))
+                    })
+                    .collect();
+                return Some(colors);
+            }
+        };
+        let max = self
+            .max
+            .unwrap_or_else(|| values.iter().map(|&(_, v)| v).fold(0., f32::max));
+        let colors = values
+            .into_iter()
+            .map(|(id, v)| (id, palette::colormap(&self.colors, v, max)))
+            .collect();
+        Some(colors)
+    }
+}
+
+#[test]
+fn color_modes() {
+    let sphere = |n: &str, x: f32| Sphere {
+        loc: (x, 0., 0.),
+        diameter: 1.,
+        names: ObjectID(vec![n.into()]),
+        vectors: BTreeMap::new(),
+    };
+    let frame = Frame {
+        spheres: vec![sphere("a", 0.), sphere("b", 0.9), sphere("c", -0.9)],
+        spherocylinders: vec![],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![],
+    };
+    assert_eq!(ColorMode::Palette.next(), ColorMode::Coordination);
+    assert_eq!(ColorMode::Coordination.next(), ColorMode::Axis);
+    assert_eq!(ColorMode::Director.next(), ColorMode::Cluster);
+    assert_eq!(ColorMode::Cluster.next(), ColorMode::Palette);
+
+    let config: ColoringConfig =
+        toml::from_str("mode = \"coordination\"\ncolors = [[0, 0, 0], [100, 200, 0]]").unwrap();
+    let analysis = AnalysisConfig::default();
+    assert_eq!(config.mode, ColorMode::Coordination);
+    assert_eq!(
+        config.colors(ColorMode::Palette, &frame, &analysis, None),
+        None
+    );
+    let colors = config.colors(config.mode, &frame, &analysis, None).unwrap();
+    assert_eq!(colors[0], (ObjectID(vec!["a".into()]), Color(100, 200, 0)));
+    assert_eq!(colors[1].1, Color(50, 100, 0));
+
+    let rod = |n: &str, axis: (f32, f32, f32)| Spherocylinder {
+        loc: (0., 0., 0.),
+        axis,
+        diameter: 0.5,
+        names: ObjectID(vec![n.into()]),
+        vectors: BTreeMap::new(),
+    };
+    let rods = Frame {
+        spherocylinders: vec![rod("x", (0., 0., 2.)), rod("y", (0., -1., 0.))],
+        ..frame
+    };
+    let axis = config
+        .colors(ColorMode::Axis, &rods, &analysis, None)
+        .unwrap();
+    assert_eq!(axis[0], (ObjectID(vec!["x".into()]), Color(0, 0, 255)));
+    assert_eq!(axis[1].1, Color(0, 255, 0));
+
+    let config: ColoringConfig =
+        toml::from_str("director = [0, 0, -3]\nangle_colors = [[0, 0, 0], [200, 100, 0]]").unwrap();
+    let angles = config
+        .colors(ColorMode::Director, &rods, &analysis, None)
+        .unwrap();
+    // Parallel and antiparallel rods look the same; perpendicular ones are halfway round
+    assert_eq!(angles[0].1, Color(0, 0, 0));
+    assert_eq!(angles[1].1, Color(200, 100, 0));
+}
@@ -1,9 +1,30 @@
 //! Serializable configuration for Parview.
+use analysis::AnalysisConfig;
+use camera::{CameraPath, Projection};
+use clusters::ClusterConfig;
+use coloring::ColoringConfig;
+use contacts::ContactConfig;
+use diff::DiffConfig;
+use keymap::Action;
+use objects::{ObjectID, SimBox};
+use overlays::{DirectorConfig, ScaleBarConfig, TriadConfig};
+use palette::Color;
 use parviewer::Config;
+use plot::PlotConfig;
+use record::RecordConfig;
 use serde;
+use snapshot::SnapshotConfig;
+use style::{LightMode, TextStyle};
+use vectors::VectorConfig;
+use video::VideoConfig;
+use viewports::ViewportConfig;
 
 use serde::{Deserialize, Serialize};
 
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+use coloring::ColorMode;
 #[cfg(test)]
 use toml;
 
@@ -16,13 +37,46 @@ struct TomlConfigOpt {
     pub yaw: Option<f32>,
     pub fov: Option<f32>,
     pub distance: Option<f32>,
+    pub projection: Option<Projection>,
+    pub extent: Option<f32>,
+    pub target: Option<(f32, f32, f32)>,
+    pub follow: Option<ObjectID>,
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub pauseloop: Option<f32>,
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    pub stride: Option<usize>,
+    pub pingpong: Option<bool>,
     pub rotate: Option<f32>,
     pub framerate: Option<f32>,
     pub fps: Option<f32>,
+    pub speeds: Option<Vec<f32>>,
+    pub timescale: Option<f32>,
+    pub interpolate: Option<bool>,
     pub showbox: Option<bool>,
+    pub background: Option<Color>,
+    pub light: Option<LightMode>,
+    pub text_format: Option<String>,
+    pub plot: Option<PlotConfig>,
+    pub triad: Option<TriadConfig>,
+    pub scale_bar: Option<ScaleBarConfig>,
+    pub director: Option<DirectorConfig>,
+    pub keys: Option<BTreeMap<String, Action>>,
+    pub camera_path: Option<CameraPath>,
+    pub text: Option<TextStyle>,
+    pub snapshot: Option<SnapshotConfig>,
+    pub video: Option<VideoConfig>,
+    pub record: Option<RecordConfig>,
+    pub viewports: Option<ViewportConfig>,
+    pub diff: Option<DiffConfig>,
+    pub vectors: Option<VectorConfig>,
+    pub contacts: Option<ContactConfig>,
+    pub clusters: Option<ClusterConfig>,
+    #[serde(rename = "box")]
+    pub sim_box: Option<SimBox>,
+    pub analysis: Option<AnalysisConfig>,
+    pub coloring: Option<ColoringConfig>,
 }
 
 /// Configuration to be loaded from the TOML file
@@ -36,20 +90,94 @@ pub struct TomlConfig {
     pub fov: f32,
     /// Set distance between camera and box (L) [default: 2]
     pub distance: f32,
+    /// Camera projection, "perspective" or "orthographic" [default: perspective]
+    pub projection: Projection,
+    /// Width of the orthographic view at the initial distance. None matches the width of the
+    /// perspective view at the target. [default: None]
+    pub extent: Option<f32>,
+    /// Point the camera looks at [default: origin]
+    pub target: (f32, f32, f32),
+    /// Keep the camera centred on the particle with these names, or on the mean position of
+    /// all particles whose names start with them [default: None]
+    pub follow: Option<ObjectID>,
     /// Set window width (pixels) [default: 600]
     pub width: u32,
     /// Set window height, if different from width (pixels)
     pub height: Option<u32>,
     /// When finished, pause for FRAMES frames, then loop. None does not loop. [default: None]
     pub pauseloop: Option<f32>,
+    /// First frame to show [default: 0]
+    pub start: usize,
+    /// Stop before this frame. None plays to the end. [default: None]
+    pub end: Option<usize>,
+    /// Show only every STRIDE-th frame [default: 1]
+    pub stride: usize,
+    /// Reverse direction at the ends, instead of wrapping around [default: false]
+    pub pingpong: bool,
     /// Continuous box rotation (angle / frame) [default: 0]
     pub rotate: f32,
     /// Framerate: sets maximum framerate of drawing, independent of actual frames. [default: 24]
     pub framerate: f32,
-    /// Rate of drawing frames. [default: 2.0]
+    /// Rate of drawing frames. In real-time mode, a multiple of `timescale`. [default: 2.0]
     pub fps: f32,
+    /// Playback speeds to step through with `,` and `.`. Their reciprocals are included as
+    /// slower speeds. [default: [1, 2, 3, 4, 6, ..., 128]]
+    pub speeds: Vec<f32>,
+    /// Real-time playback: simulation time units per second at speed 1. Requires every frame
+    /// to have a `time`. None plays frame-by-frame. [default: None]
+    pub timescale: Option<f32>,
+    /// Move particles smoothly between frames when playing slower than one frame per tick,
+    /// matching them by name. [default: false]
+    pub interpolate: bool,
     /// Show the box
     pub showbox: bool,
+    /// Background color [default: black]
+    pub background: Color,
+    /// Where the scene is lit from [default: stick-to-camera]
+    pub light: LightMode,
+    /// Template for the frame text, e.g. "t={time:.3} φ={phi:.4}". Names can be `time`,
+    /// `step`, `index`, `text`, or any key in a frame's `values`. [default: None, which shows
+    /// the frame's text]
+    pub text_format: Option<String>,
+    /// A plot of per-frame values, drawn in a corner of the window. [default: None]
+    pub plot: Option<PlotConfig>,
+    /// An axis triad, rotating with the camera. [default: None]
+    pub triad: Option<TriadConfig>,
+    /// A scale bar in simulation length units. [default: None]
+    pub scale_bar: Option<ScaleBarConfig>,
+    /// The nematic director of the spherocylinders and their order parameter. [default: None]
+    pub director: Option<DirectorConfig>,
+    /// Key bindings, mapping key names like "Q" or "Shift+/" to actions like "quit". These
+    /// override the default bindings. [default: no overrides]
+    pub keys: BTreeMap<String, Action>,
+    /// Scripted camera keyframes, applied as the frames play [default: no keyframes]
+    pub camera_path: CameraPath,
+    /// Font, size, color and position of the frame text
+    pub text: TextStyle,
+    /// Size, background and file names of snapshots
+    pub snapshot: SnapshotConfig,
+    /// Format and encoder settings for `pvrecord`
+    pub video: VideoConfig,
+    /// Frame-exact recording for `pvrecord`
+    pub record: RecordConfig,
+    /// Layout of the viewports, when comparing trajectories
+    pub viewports: ViewportConfig,
+    /// Color particles by how far they have moved from a reference frame [default: None]
+    pub diff: Option<DiffConfig>,
+    /// How vectors of the particles, such as velocities, are drawn as arrows
+    pub vectors: VectorConfig,
+    /// How contacts between particles are found and drawn
+    pub contacts: ContactConfig,
+    /// How clusters of touching particles are found and colored
+    pub clusters: ClusterConfig,
+    /// The box around the particles, and whether it is periodic [default: None]
+    #[serde(rename = "box")]
+    pub sim_box: Option<SimBox>,
+    /// Statistics added to the values of each frame, such as the packing fraction
+    /// [default: None]
+    pub analysis: Option<AnalysisConfig>,
+    /// Coloring by properties of the particles, instead of by name
+    pub coloring: ColoringConfig,
 }
 
 impl Default for TomlConfig {
@@ -59,13 +187,47 @@ impl Default for TomlConfig {
             yaw: 0.,
             fov: 45.,
             distance: 2.,
+            projection: Projection::Perspective,
+            extent: None,
+            target: (0., 0., 0.),
+            follow: None,
             width: 800,
             height: None,
             pauseloop: None,
+            start: 0,
+            end: None,
+            stride: 1,
+            pingpong: false,
             rotate: 0.0,
             fps: 2.0,
             framerate: 24.0,
+            speeds: vec![
+                1., 2., 3., 4., 6., 8., 12., 16., 24., 32., 48., 64., 96., 128.,
+            ],
+            timescale: None,
+            interpolate: false,
             showbox: true,
+            background: Color(0, 0, 0),
+            light: LightMode::StickToCamera,
+            text_format: None,
+            plot: None,
+            triad: None,
+            scale_bar: None,
+            director: None,
+            keys: BTreeMap::new(),
+            camera_path: CameraPath::default(),
+            text: TextStyle::default(),
+            snapshot: SnapshotConfig::default(),
+            video: VideoConfig::default(),
+            record: RecordConfig::default(),
+            viewports: ViewportConfig::default(),
+            diff: None,
+            vectors: VectorConfig::default(),
+            contacts: ContactConfig::default(),
+            clusters: ClusterConfig::default(),
+            sim_box: None,
+            analysis: None,
+            coloring: ColoringConfig::default(),
         }
     }
 }
@@ -84,13 +246,45 @@ impl TomlConfig {
             yaw: tco.yaw.unwrap_or(default_opts.yaw),
             fov: tco.fov.unwrap_or(default_opts.fov),
             distance: tco.distance.unwrap_or(default_opts.distance),
+            projection: tco.projection.unwrap_or(default_opts.projection),
+            extent: tco.extent,
+            target: tco.target.unwrap_or(default_opts.target),
+            follow: tco.follow,
             width: tco.width.unwrap_or(default_opts.width),
             height: tco.height,
             pauseloop: tco.pauseloop,
+            start: tco.start.unwrap_or(default_opts.start),
+            end: tco.end,
+            stride: tco.stride.unwrap_or(default_opts.stride),
+            pingpong: tco.pingpong.unwrap_or(default_opts.pingpong),
             rotate: tco.rotate.unwrap_or(default_opts.rotate),
             fps: tco.fps.unwrap_or(default_opts.fps),
             framerate: tco.framerate.unwrap_or(default_opts.framerate),
+            speeds: tco.speeds.unwrap_or(default_opts.speeds),
+            timescale: tco.timescale,
+            interpolate: tco.interpolate.unwrap_or(default_opts.interpolate),
             showbox: tco.showbox.unwrap_or(default_opts.showbox),
+            background: tco.background.unwrap_or(default_opts.background),
+            light: tco.light.unwrap_or(default_opts.light),
+            text_format: tco.text_format,
+            plot: tco.plot,
+            triad: tco.triad,
+            scale_bar: tco.scale_bar,
+            director: tco.director,
+            keys: tco.keys.unwrap_or(default_opts.keys),
+            camera_path: tco.camera_path.unwrap_or(default_opts.camera_path),
+            text: tco.text.unwrap_or(default_opts.text),
+            snapshot: tco.snapshot.unwrap_or(default_opts.snapshot),
+            video: tco.video.unwrap_or(default_opts.video),
+            record: tco.record.unwrap_or(default_opts.record),
+            viewports: tco.viewports.unwrap_or(default_opts.viewports),
+            diff: tco.diff,
+            vectors: tco.vectors.unwrap_or(default_opts.vectors),
+            contacts: tco.contacts.unwrap_or(default_opts.contacts),
+            clusters: tco.clusters.unwrap_or(default_opts.clusters),
+            sim_box: tco.sim_box,
+            analysis: tco.analysis,
+            coloring: tco.coloring.unwrap_or(default_opts.coloring),
         }
     }
 
@@ -103,9 +297,39 @@ impl TomlConfig {
             width: self.width,
             height: self.height.unwrap_or(self.width),
             distance: self.distance,
+            projection: self.projection,
+            extent: self.extent,
+            target: self.target,
+            follow: self.follow.clone(),
             pauseloop: self.pauseloop,
+            start: self.start,
+            end: self.end,
+            stride: self.stride,
+            pingpong: self.pingpong,
             framerate: self.framerate,
+            speeds: self.speeds.clone(),
+            timescale: self.timescale,
+            interpolate: self.interpolate,
             showbox: self.showbox,
+            background: self.background,
+            light: self.light,
+            text_format: self.text_format.clone(),
+            plot: self.plot.clone(),
+            triad: self.triad.clone(),
+            scale_bar: self.scale_bar.clone(),
+            director: self.director.clone(),
+            keys: self.keys.clone(),
+            camera_path: self.camera_path.clone(),
+            text: self.text.clone(),
+            snapshot: self.snapshot.clone(),
+            viewports: self.viewports.clone(),
+            diff: self.diff.clone(),
+            vectors: self.vectors.clone(),
+            contacts: self.contacts.clone(),
+            clusters: self.clusters.clone(),
+            sim_box: self.sim_box,
+            analysis: self.analysis.clone(),
+            coloring: self.coloring.clone(),
         }
     }
 }
@@ -116,3 +340,114 @@ fn config_toml_empty_string() {
     let c2: TomlConfig = toml::from_slice(b"").unwrap();
     assert_eq!(c, c2);
 }
+
+#[test]
+fn config_toml_range() {
+    let s = "
+        start = 200
+        end = 451
+        stride = 5
+        pingpong = true
+    ";
+    let c: TomlConfig = toml::from_str(s).unwrap();
+    assert_eq!(c.start, 200);
+    assert_eq!(c.end, Some(451));
+    assert_eq!(c.stride, 5);
+    assert!(c.pingpong);
+}
+
+#[test]
+fn config_toml_view() {
+    let s = "
+        target = [0.5, 0.5, 0.0]
+        projection = \"orthographic\"
+        follow = [\"2\", \"3\"]
+    ";
+    let c: TomlConfig = toml::from_str(s).unwrap();
+    assert_eq!(c.target, (0.5, 0.5, 0.));
+    assert_eq!(c.projection, Projection::Orthographic);
+    assert_eq!(c.extent, None);
+    assert_eq!(c.follow, Some(ObjectID(vec!["2".into(), "3".into()])));
+    assert_eq!(TomlConfig::default().follow, None);
+}
+
+#[test]
+fn config_toml_plot() {
+    let s = "
+        fps = 4.0
+
+        [plot]
+        values = [\"energy\", \"phi\"]
+        size = [0.5, 0.25]
+    ";
+    let c: TomlConfig = toml::from_str(s).unwrap();
+    let plot = c.plot.unwrap();
+    assert_eq!(plot.values, vec!["energy", "phi"]);
+    assert_eq!(plot.size, (0.5, 0.25));
+    assert_eq!(plot.position, PlotConfig::default().position);
+    assert_eq!(c.triad, None);
+}
+
+#[test]
+fn config_toml_overlays() {
+    let s = "
+        [triad]
+
+        [scale_bar]
+        units = \"σ\"
+        color = [0, 0, 0]
+
+        [director]
+        size = 0.1
+    ";
+    let c: TomlConfig = toml::from_str(s).unwrap();
+    assert_eq!(c.triad, Some(TriadConfig::default()));
+    let scale_bar = c.scale_bar.unwrap();
+    assert_eq!(scale_bar.units, "σ");
+    assert_eq!(scale_bar.color, Color(0, 0, 0));
+    assert_eq!(scale_bar.length, ScaleBarConfig::default().length);
+    let director = c.director.unwrap();
+    assert_eq!(director.size, 0.1);
+    assert_eq!(director.position, DirectorConfig::default().position);
+}
+
+#[test]
+fn config_toml_style() {
+    let s = "
+        background = [255, 255, 255]
+
+        [text]
+        color = [0, 0, 0]
+        font_size = 32.0
+    ";
+    let c: TomlConfig = toml::from_str(s).unwrap();
+    assert_eq!(c.background, Color(255, 255, 255));
+    assert_eq!(c.light, LightMode::StickToCamera);
+    assert_eq!(c.text.color, Color(0, 0, 0));
+    assert_eq!(c.text.font_size, 32.);
+    assert_eq!(c.text.position, TextStyle::default().position);
+    assert_eq!(c.snapshot, SnapshotConfig::default());
+    assert_eq!(c.video, VideoConfig::default());
+}
+
+#[test]
+fn config_toml_analysis() {
+    let s = "
+        [box]
+        size = [2.0, 2.0, 4.0]
+        periodic = true
+
+        [analysis]
+        tolerance = 0.01
+
+        [coloring]
+        mode = \"coordination\"
+    ";
+    let c: TomlConfig = toml::from_str(s).unwrap();
+    let sim_box = c.sim_box.unwrap();
+    assert_eq!(sim_box.volume(), 16.);
+    assert!(sim_box.periodic);
+    assert_eq!(c.analysis.unwrap().tolerance, 0.01);
+    assert_eq!(c.coloring.mode, ColorMode::Coordination);
+    assert_eq!(TomlConfig::default().sim_box, None);
+}
@@ -0,0 +1,535 @@
+//! Contacts between touching or overlapping particles, for drawing contact networks and force
+//! chains in granular packings.
+//!
+//! Contacts are found with a cell list, so each particle is only compared with those in nearby
+//! cells.
+
+use na;
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use objects::{Bond, Frame, ObjectID, SimBox, EPSILON};
+use palette;
+use palette::Color;
+
+#[cfg(test)]
+use objects::{Sphere, Spherocylinder};
+#[cfg(test)]
+use rand::rngs::StdRng;
+#[cfg(test)]
+use rand::{Rng, SeedableRng};
+#[cfg(test)]
+use std::collections::BTreeMap;
+#[cfg(test)]
+use toml;
+
+/// A force between two particles, given with a frame, e.g. from the simulation
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ContactForce {
+    /// The names of the two particles, in either order
+    pub between: (ObjectID, ObjectID),
+    /// Magnitude of the force
+    pub force: f32,
+}
+
+/// Two particles that touch, overlap, or are closer than the tolerance
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+    /// The names of the two particles, in order
+    pub between: (ObjectID, ObjectID),
+    /// The centres of the two particles; for a contact through the walls of a periodic box, the
+    /// second is where the first sees it, outside the box
+    pub centres: (na::Point3<f32>, na::Point3<f32>),
+    /// How far the particles overlap; negative for a gap
+    pub overlap: f32,
+    /// The force between them, if the frame gives one
+    pub force: Option<f32>,
+}
+
+/// A particle as its core segment (a point, for a sphere) and radius
+struct Body<'a> {
+    names: &'a ObjectID,
+    centre: na::Point3<f32>,
+    ends: (na::Point3<f32>, na::Point3<f32>),
+    radius: f32,
+    /// Radius of a sphere around the centre that holds the whole particle
+    reach: f32,
+}
+
+fn bodies(frame: &Frame) -> Vec<Body<'_>> {
+    let spheres = frame.spheres.iter().map(|s| {
+        let x = na::Point3::from(s.x());
+        Body {
+            names: &s.names,
+            centre: x,
+            ends: (x, x),
+            radius: s.diameter / 2.,
+            reach: s.diameter / 2.,
+        }
+    });
+    let spherocylinders = frame.spherocylinders.iter().map(|s| {
+        let x = na::Point3::from(s.x());
+        let axis = s.get_axis();
+        let length = axis.norm();
+        // The axis runs from cap to cap; the core segment is shorter by a diameter
+        let half = if length > s.diameter {
+            axis * ((length - s.diameter) / (2. * length))
+        } else {
+            na::Vector3::zeros()
+        };
+        Body {
+            names: &s.names,
+            centre: x,
+            ends: (x - half, x + half),
+            radius: s.diameter / 2.,
+            reach: half.norm() + s.diameter / 2.,
+        }
+    });
+    spheres.chain(spherocylinders).collect()
+}
+
+/// The closest points of the segments from `p1` to `q1` and from `p2` to `q2`
+fn closest_points(
+    (p1, q1): (na::Point3<f32>, na::Point3<f32>),
+    (p2, q2): (na::Point3<f32>, na::Point3<f32>),
+) -> (na::Point3<f32>, na::Point3<f32>) {
+    let (d1, d2, r) = (q1 - p1, q2 - p2, p1 - p2);
+    let (a, e, f) = (d1.dot(&d1), d2.dot(&d2), d2.dot(&r));
+    let (s, t) = if a <= EPSILON && e <= EPSILON {
+        (0., 0.)
+    } else if a <= EPSILON {
+        (0., (f / e).clamp(0., 1.))
+    } else {
+        let c = d1.dot(&r);
+        if e <= EPSILON {
+            ((-c / a).clamp(0., 1.), 0.)
+        } else {
+            let b = d1.dot(&d2);
+            let denom = a * e - b * b;
+            // Parallel segments have no single closest pair; any point will do
+            let s = if denom > EPSILON {
+                ((b * f - c * e) / denom).clamp(0., 1.)
+            } else {
+                0.
+            };
+            let t = (b * s + f) / e;
+            if t < 0. {
+                ((-c / a).clamp(0., 1.), 0.)
+            } else if t > 1. {
+                (((b - c) / a).clamp(0., 1.), 1.)
+            } else {
+                (s, t)
+            }
+        }
+    };
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+/// Points binned into cubic cells, which wrap around a periodic box
+struct CellList {
+    /// Side of a cell
+    size: f32,
+    /// For a periodic box, its lengths, and the number of cells along each
+    wrap: Option<(na::Vector3<f32>, [i64; 3])>,
+    /// The indices of the points in each cell
+    cells: HashMap<[i64; 3], Vec<usize>>,
+}
+
+impl CellList {
+    fn new(points: &[na::Point3<f32>], size: f32, sim_box: Option<&SimBox>) -> CellList {
+        let size = size.max(EPSILON);
+        // A periodic box is divided into a whole number of cells along each side, so that the
+        // cells wrap around with it
+        let wrap = sim_box.filter(|b| b.periodic).map(|b| {
+            let lengths = b.lengths();
+            let mut counts = [1; 3];
+            for (i, n) in counts.iter_mut().enumerate() {
+                *n = ((lengths[i] / size).floor() as i64).max(1);
+            }
+            (lengths, counts)
+        });
+        let mut list = CellList {
+            size,
+            wrap,
+            cells: HashMap::new(),
+        };
+        for (i, x) in points.iter().enumerate() {
+            let key = list.key(x);
+            list.cells.entry(key).or_default().push(i);
+        }
+        list
+    }
+
+    /// The cell holding a point
+    fn key(&self, x: &na::Point3<f32>) -> [i64; 3] {
+        let mut key = [0; 3];
+        for (i, k) in key.iter_mut().enumerate() {
+            *k = match self.wrap {
+                Some((lengths, counts)) => {
+                    let fraction = (x[i] / lengths[i].max(EPSILON) + 0.5).rem_euclid(1.);
+                    ((fraction * counts[i] as f32) as i64).min(counts[i] - 1)
+                }
+                None => (x[i] / self.size).floor() as i64,
+            };
+        }
+        key
+    }
+
+    /// Whether a cell is at most `reach` cells from `home` along each axis
+    fn within(&self, key: &[i64; 3], home: &[i64; 3], reach: i64) -> bool {
+        (0..3).all(|i| {
+            let d = (key[i] - home[i]).abs();
+            match self.wrap {
+                Some((_, counts)) => d.min(counts[i] - d) <= reach,
+                None => d <= reach,
+            }
+        })
+    }
+
+    /// The points in the cells at most `reach` cells from `home` along each axis
+    fn near(&self, home: &[i64; 3], reach: i64) -> Vec<&[usize]> {
+        let width = 2. * reach as f64 + 1.;
+        if width * width * width > self.cells.len() as f64 {
+            // Fewer cells hold points than are in reach, so check each of those
+            return self
+                .cells
+                .iter()
+                .filter(|&(key, _)| self.within(key, home, reach))
+                .map(|(_, points)| &points[..])
+                .collect();
+        }
+        let mut keys = vec![];
+        for dx in -reach..=reach {
+            for dy in -reach..=reach {
+                for dz in -reach..=reach {
+                    let mut key = [home[0] + dx, home[1] + dy, home[2] + dz];
+                    if let Some((_, counts)) = self.wrap {
+                        for (k, &n) in key.iter_mut().zip(counts.iter()) {
+                            *k = k.rem_euclid(n);
+                        }
+                    }
+                    keys.push(key);
+                }
+            }
+        }
+        // A periodic box only a few cells across reaches the same cell more than once
+        keys.sort();
+        keys.dedup();
+        keys.iter()
+            .filter_map(|key| self.cells.get(key))
+            .map(|points| &points[..])
+            .collect()
+    }
+}
+
+/// Each pair of `points` at most `reaches[i] + reaches[j] + tolerance` apart: their indices,
+/// the lower first, and the separation from the first to the second.
+///
+/// The cells are sized for the median reach, so most points are only compared with those in
+/// neighbouring cells. Each pair is checked from the point with the larger reach, which looks
+/// as many cells further as it needs, so a few large particles do not make every cell large.
+fn reach_pairs(
+    points: &[na::Point3<f32>],
+    reaches: &[f32],
+    tolerance: f32,
+    sim_box: Option<&SimBox>,
+) -> Vec<(usize, usize, na::Vector3<f32>)> {
+    let mut sorted = reaches.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let median = sorted.get(sorted.len() / 2).cloned().unwrap_or(0.);
+    let cells = CellList::new(points, 2. * median + tolerance, sim_box);
+    let sim_box = sim_box.filter(|b| b.periodic);
+    // Which of two points checks the pair: the larger, or the later of two of the same size
+    let checks =
+        |i: usize, j: usize| reaches[i] > reaches[j] || (reaches[i] == reaches[j] && i > j);
+
+    let mut pairs = vec![];
+    for (i, x) in points.iter().enumerate() {
+        let reach = ((2. * reaches[i] + tolerance) / cells.size).ceil().max(1.) as i64;
+        for neighbours in cells.near(&cells.key(x), reach) {
+            for &j in neighbours.iter().filter(|&&j| checks(i, j)) {
+                let separation = match sim_box {
+                    Some(b) => b.separation(x, &points[j]),
+                    None => points[j] - x,
+                };
+                if separation.norm() > reaches[i] + reaches[j] + tolerance {
+                    continue;
+                }
+                if i < j {
+                    pairs.push((i, j, separation));
+                } else {
+                    pairs.push((j, i, -separation));
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Each pair of `points` at most `cutoff` apart, found with a cell list: their indices, the
+/// lower first, and the separation from the first to the second. In a periodic box, points are
+/// neighbours through the walls too; the cutoff should then be under half the box.
+pub fn neighbour_pairs(
+    points: &[na::Point3<f32>],
+    cutoff: f32,
+    sim_box: Option<&SimBox>,
+) -> Vec<(usize, usize, na::Vector3<f32>)> {
+    let reaches = vec![cutoff / 2.; points.len()];
+    reach_pairs(points, &reaches, 0., sim_box)
+}
+
+/// The contacts between the spheres and spherocylinders of `frame`: each pair of particles
+/// that overlap, touch, or have a gap of at most `tolerance`, through the walls of a periodic
+/// box. Forces given with the frame are attached to the contacts they belong to.
+pub fn find_contacts(frame: &Frame, tolerance: f32, sim_box: Option<&SimBox>) -> Vec<Contact> {
+    let bodies = bodies(frame);
+    let centres: Vec<_> = bodies.iter().map(|b| b.centre).collect();
+    let reaches: Vec<_> = bodies.iter().map(|b| b.reach).collect();
+
+    let forces: HashMap<(&ObjectID, &ObjectID), f32> = frame
+        .forces
+        .iter()
+        .flat_map(|f| {
+            let (ref a, ref b) = f.between;
+            vec![((a, b), f.force), ((b, a), f.force)]
+        })
+        .collect();
+
+    let mut contacts = vec![];
+    for (i, j, separation) in reach_pairs(&centres, &reaches, tolerance, sim_box) {
+        let (body, other) = (&bodies[i], &bodies[j]);
+        // The other particle where this one sees it, through the walls of a periodic box
+        let shift = body.centre + separation - other.centre;
+        let (a, b) = closest_points(body.ends, (other.ends.0 + shift, other.ends.1 + shift));
+        let overlap = body.radius + other.radius - (b - a).norm();
+        if overlap < -tolerance {
+            continue;
+        }
+        let (first, second, centres) = if body.names <= other.names {
+            (body, other, (body.centre, body.centre + separation))
+        } else {
+            (other, body, (other.centre, other.centre - separation))
+        };
+        contacts.push(Contact {
+            between: (first.names.clone(), second.names.clone()),
+            centres,
+            overlap,
+            force: forces.get(&(first.names, second.names)).cloned(),
+        });
+    }
+    // Sorted, so the result does not depend on the order of the cells
+    contacts.sort_by(|a, b| a.between.cmp(&b.between));
+    contacts
+}
+
+/// What sets the thickness and color of a contact
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContactScale {
+    /// How far the particles overlap
+    Overlap,
+    /// The force given with the frame; contacts without one are drawn as if it were 0
+    Force,
+}
+
+/// How contacts are found and drawn
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct ContactConfig {
+    /// Draw the contacts from the start; the N key turns them on and off.
+    pub show: bool,
+    /// Largest gap between two particles still counted as a contact
+    pub tolerance: f32,
+    /// Scale the contacts by overlap, or by the forces given with the frames
+    pub scale: ContactScale,
+    /// Value at the top of the scale. None uses the largest in each frame.
+    pub max: Option<f32>,
+    /// Diameter of a contact at the bottom of the scale, and at the top
+    pub width: (f32, f32),
+    /// Colors from the bottom of the scale up to `max`, evenly spaced
+    pub colors: Vec<Color>,
+}
+
+impl Default for ContactConfig {
+    fn default() -> Self {
+        ContactConfig {
+            show: false,
+            tolerance: 0.,
+            scale: ContactScale::Overlap,
+            max: None,
+            width: (0.005, 0.05),
+            colors: vec![Color(255, 255, 255), Color(255, 127, 0), Color(228, 26, 28)],
+        }
+    }
+}
+
+impl ContactConfig {
+    /// The value of a contact on the scale
+    pub fn value(&self, contact: &Contact) -> f32 {
+        match self.scale {
+            ContactScale::Overlap => contact.overlap.max(0.),
+            ContactScale::Force => contact.force.map_or(0., f32::abs),
+        }
+    }
+
+    /// A bond drawn between the centres of the particles of each contact, sized and colored
+    /// by its value.
+    pub fn bonds(&self, contacts: &[Contact]) -> Vec<Bond> {
+        let max = self
+            .max
+            .unwrap_or_else(|| contacts.iter().map(|c| self.value(c)).fold(0., f32::max));
+        let (thin, thick) = self.width;
+        contacts
+            .iter()
+            .map(|c| {
+                let value = self.value(c);
+                let fraction = if max > EPSILON {
+                    (value / max).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                let (ref a, ref b) = c.between;
+                // Named after both particles, so each bond stays the same object between frames
+                let mut names = a.0.clone();
+                names.push("contact".into());
+                names.extend(b.0.iter().cloned());
+                let (from, to) = c.centres;
+                Bond {
+                    from: (from.x, from.y, from.z),
+                    to: (to.x, to.y, to.z),
+                    width: thin + (thick - thin) * fraction,
+                    color: palette::colormap(&self.colors, value, max),
+                    names: ObjectID(names),
+                }
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn contacts() {
+    let names = |n: &str| ObjectID(vec![n.into()]);
+    let sphere = |n: &str, x: f32| Sphere {
+        loc: (x, 0., 0.),
+        diameter: 1.,
+        names: names(n),
+        vectors: BTreeMap::new(),
+    };
+    let frame = Frame {
+        spheres: vec![
+            sphere("a", 0.),
+            sphere("b", 0.95),
+            sphere("c", 3.),
+            sphere("d", 4.05),
+        ],
+        // Lying across a and b, overlapping each by 0.05
+        spherocylinders: vec![Spherocylinder {
+            loc: (0.5, 0.7, 0.),
+            axis: (3., 0., 0.),
+            diameter: 0.5,
+            names: names("rod"),
+            vectors: BTreeMap::new(),
+        }],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![ContactForce {
+            between: (names("rod"), names("a")),
+            force: 2.,
+        }],
+    };
+
+    let contacts = find_contacts(&frame, 0., None);
+    let pairs: Vec<_> = contacts.iter().map(|c| c.between.clone()).collect();
+    assert_eq!(
+        pairs,
+        vec![
+            (names("a"), names("b")),
+            (names("a"), names("rod")),
+            (names("b"), names("rod")),
+        ]
+    );
+    assert!((contacts[0].overlap - 0.05).abs() < 1e-5);
+    assert!((contacts[1].overlap - 0.05).abs() < 1e-5);
+    assert_eq!(contacts[1].force, Some(2.));
+    assert_eq!(contacts[2].force, None);
+    // c and d have a gap of 0.05
+    let contacts = find_contacts(&frame, 0.1, None);
+    assert_eq!(contacts.len(), 4);
+    assert!((contacts[3].overlap + 0.05).abs() < 1e-5);
+
+    let config: ContactConfig =
+        toml::from_str("scale = \"force\"\nwidth = [0.0, 1.0]\ncolors = [[0, 0, 0], [200, 0, 0]]")
+            .unwrap();
+    let bonds = config.bonds(&contacts);
+    assert_eq!(bonds[1].width, 1.);
+    assert_eq!(bonds[1].color, Color(200, 0, 0));
+    assert_eq!(bonds[0].width, 0.);
+    assert_eq!(bonds[1].names.0, vec!["a", "contact", "rod"]);
+
+    // Through the walls of a periodic box
+    let sim_box = SimBox {
+        size: (10., 10., 10.),
+        periodic: true,
+    };
+    let wrapped = Frame {
+        spheres: vec![sphere("a", -4.9), sphere("e", 4.85)],
+        spherocylinders: vec![],
+        forces: vec![],
+        ..frame.clone()
+    };
+    assert!(find_contacts(&wrapped, 0., None).is_empty());
+    let contacts = find_contacts(&wrapped, 0., Some(&sim_box));
+    assert_eq!(contacts.len(), 1);
+    assert!((contacts[0].overlap - 0.75).abs() < 1e-4);
+    assert!((contacts[0].centres.1.x + 5.15).abs() < 1e-4);
+
+    // The cell list finds the same contacts as comparing every pair, with a few large spheres
+    // among the small ones
+    let mut rng = StdRng::seed_from_u64(46);
+    let spheres: Vec<Sphere> = (0..300)
+        .map(|i| Sphere {
+            loc: (
+                rng.gen::<f32>() * 6. - 3.,
+                rng.gen::<f32>() * 6. - 3.,
+                rng.gen::<f32>() * 6. - 3.,
+            ),
+            diameter: if i % 100 == 0 {
+                2.
+            } else {
+                0.3 + rng.gen::<f32>() * 0.5
+            },
+            names: names(&format!("{:03}", i)),
+            vectors: BTreeMap::new(),
+        })
+        .collect();
+    let sim_box = SimBox {
+        size: (6., 6., 6.),
+        periodic: true,
+    };
+    let (mut expected, mut expected_periodic) = (0, 0);
+    for (i, a) in spheres.iter().enumerate() {
+        for b in &spheres[i + 1..] {
+            let reach = (a.diameter + b.diameter) / 2. + 0.1;
+            if (a.x() - b.x()).norm() <= reach {
+                expected += 1;
+            }
+            let (x, y) = (na::Point3::from(a.x()), na::Point3::from(b.x()));
+            if sim_box.separation(&x, &y).norm() <= reach {
+                expected_periodic += 1;
+            }
+        }
+    }
+    let frame = Frame {
+        spheres,
+        spherocylinders: vec![],
+        forces: vec![],
+        ..frame
+    };
+    assert_eq!(find_contacts(&frame, 0.1, None).len(), expected);
+    let periodic = find_contacts(&frame, 0.1, Some(&sim_box));
+    assert_eq!(periodic.len(), expected_periodic);
+}
@@ -0,0 +1,256 @@
+//! Comparing each frame with a reference: particles colored by how far they have moved, and
+//! those that appeared or disappeared highlighted.
+//!
+//! The reference is a frame of the same trajectory, or the same frame of another trajectory
+//! of the same system. Particles are matched by name.
+
+use na;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashMap};
+
+use objects::{Frame, ObjectID, EPSILON};
+use palette;
+use palette::Color;
+
+#[cfg(test)]
+use objects::Sphere;
+#[cfg(test)]
+use toml;
+
+/// How frames are compared with the reference, and colored
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct DiffConfig {
+    /// Frame of the trajectory to compare each frame with. With a reference trajectory, each
+    /// frame is compared with the same frame of that trajectory instead.
+    pub reference: usize,
+    /// Displacement at the top of the color scale. None uses the largest in each frame.
+    pub max: Option<f32>,
+    /// Colors from no displacement up to `max`, evenly spaced
+    pub colors: Vec<Color>,
+    /// Particles that are not in the reference
+    pub appeared: Color,
+    /// Particles of the reference that are missing, drawn where they were
+    pub disappeared: Color,
+    /// Draw an arrow from where each particle was to where it is
+    pub arrows: bool,
+    /// Shortest displacement drawn as an arrow
+    pub min_arrow: f32,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        DiffConfig {
+            reference: 0,
+            max: None,
+            colors: vec![Color(49, 54, 149), Color(255, 255, 191), Color(165, 0, 38)],
+            appeared: Color(77, 175, 74),
+            disappeared: Color(152, 78, 163),
+            arrows: false,
+            min_arrow: 0.,
+        }
+    }
+}
+
+impl DiffConfig {
+    /// The color for a displacement of `distance`, where `max` is the top of the scale
+    pub fn color(&self, distance: f32, max: f32) -> Color {
+        palette::colormap(&self.colors, distance, max.max(EPSILON))
+    }
+
+    /// The top of the color scale for a frame: `max`, or the largest displacement
+    pub fn scale_max(&self, diff: &FrameDiff) -> f32 {
+        self.max.unwrap_or_else(|| diff.max_displacement())
+    }
+
+    /// The color of each particle of the frame, and of each particle in `diff.disappeared`
+    pub fn colors(&self, diff: &FrameDiff) -> Vec<(ObjectID, Color)> {
+        let max = self.scale_max(diff);
+        let moved = diff
+            .moved
+            .iter()
+            .map(|&(ref id, _, d)| (id.clone(), self.color(d.norm(), max)));
+        let appeared = diff.appeared.iter().map(|id| (id.clone(), self.appeared));
+        let disappeared = diff
+            .disappeared
+            .spheres
+            .iter()
+            .map(|s| &s.names)
+            .chain(diff.disappeared.spherocylinders.iter().map(|s| &s.names))
+            .map(|id| (id.clone(), self.disappeared));
+        moved.chain(appeared).chain(disappeared).collect()
+    }
+}
+
+/// The positions of the objects in a frame, by name
+fn positions(frame: &Frame) -> HashMap<&ObjectID, na::Point3<f32>> {
+    let spheres = frame
+        .spheres
+        .iter()
+        .map(|s| (&s.names, na::Point3::from(s.x())));
+    let spherocylinders = frame
+        .spherocylinders
+        .iter()
+        .map(|s| (&s.names, na::Point3::from(s.x())));
+    spheres.chain(spherocylinders).collect()
+}
+
+/// How a frame differs from its reference
+#[derive(Clone)]
+pub struct FrameDiff {
+    /// Each particle in both frames: where it was in the reference, and how far it has moved
+    pub moved: Vec<(ObjectID, na::Point3<f32>, na::Vector3<f32>)>,
+    /// Particles that are not in the reference
+    pub appeared: Vec<ObjectID>,
+    /// The particles of the reference that are missing from the frame
+    pub disappeared: Frame,
+}
+
+impl FrameDiff {
+    /// Compare `frame` with `reference`
+    pub fn new(reference: &Frame, frame: &Frame) -> FrameDiff {
+        let before = positions(reference);
+        let after = positions(frame);
+        let mut moved = vec![];
+        let mut appeared = vec![];
+        for (&id, &x) in &after {
+            match before.get(id) {
+                Some(&x0) => moved.push((id.clone(), x0, x - x0)),
+                None => appeared.push(id.clone()),
+            }
+        }
+        // Sorted, so arrows and colors do not depend on the order of the hash maps
+        moved.sort_by(|a, b| a.0.cmp(&b.0));
+        appeared.sort();
+
+        let disappeared = Frame {
+            spheres: reference
+                .spheres
+                .iter()
+                .filter(|s| !after.contains_key(&s.names))
+                .cloned()
+                .collect(),
+            spherocylinders: reference
+                .spherocylinders
+                .iter()
+                .filter(|s| !after.contains_key(&s.names))
+                .cloned()
+                .collect(),
+            text: String::new(),
+            time: reference.time,
+            step: reference.step,
+            values: BTreeMap::new(),
+            forces: vec![],
+        };
+
+        FrameDiff {
+            moved,
+            appeared,
+            disappeared,
+        }
+    }
+
+    /// The largest displacement of any particle
+    pub fn max_displacement(&self) -> f32 {
+        self.moved
+            .iter()
+            .map(|&(_, _, d)| d.norm())
+            .fold(0., f32::max)
+    }
+
+    /// The frame with the missing particles of the reference added back, where they were
+    pub fn with_disappeared(&self, frame: &Frame) -> Frame {
+        let mut shown = frame.clone();
+        shown
+            .spheres
+            .extend(self.disappeared.spheres.iter().cloned());
+        shown
+            .spherocylinders
+            .extend(self.disappeared.spherocylinders.iter().cloned());
+        shown
+    }
+}
+
+/// The lines of an arrow from `from` to `to`: the shaft, and a head turned to face a camera
+/// looking along `view`.
+pub fn arrow_lines(
+    from: na::Point3<f32>,
+    to: na::Point3<f32>,
+    view: &na::Vector3<f32>,
+) -> Vec<(na::Point3<f32>, na::Point3<f32>)> {
+    let shaft = to - from;
+    let length = shaft.norm();
+    if length <= EPSILON {
+        return vec![];
+    }
+    let along = shaft / length;
+    let across = along.cross(view);
+    let across = if across.norm() > EPSILON {
+        across.normalize()
+    } else {
+        // Pointing straight at the camera; any direction will do
+        along
+            .cross(&na::Vector3::x())
+            .try_normalize(EPSILON)
+            .unwrap_or_else(na::Vector3::y)
+    };
+    let head = length * 0.25;
+    let back = to - along * head;
+    vec![
+        (from, to),
+        (to, back + across * head * 0.5),
+        (to, back - across * head * 0.5),
+    ]
+}
+
+#[test]
+fn frame_diff() {
+    let sphere = |name: &str, x: f32| Sphere {
+        loc: (x, 0., 0.),
+        diameter: 1.,
+        names: ObjectID(vec![name.into()]),
+        vectors: BTreeMap::new(),
+    };
+    let frame = |spheres: Vec<Sphere>| Frame {
+        spheres,
+        spherocylinders: vec![],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![],
+    };
+    let reference = frame(vec![sphere("a", 0.), sphere("b", 1.), sphere("c", 2.)]);
+    let current = frame(vec![sphere("a", 0.), sphere("b", 3.), sphere("d", 5.)]);
+
+    let diff = FrameDiff::new(&reference, &current);
+    assert_eq!(diff.moved.len(), 2);
+    assert_eq!(diff.moved[1].0, ObjectID(vec!["b".into()]));
+    assert_eq!(diff.moved[1].1, na::Point3::new(1., 0., 0.));
+    assert_eq!(diff.max_displacement(), 2.);
+    assert_eq!(diff.appeared, vec![ObjectID(vec!["d".into()])]);
+    assert_eq!(diff.disappeared.spheres.len(), 1);
+    assert_eq!(diff.disappeared.spheres[0].loc, (2., 0., 0.));
+    assert_eq!(diff.with_disappeared(&current).spheres.len(), 4);
+
+    let config: DiffConfig = toml::from_str("colors = [[0, 0, 0], [200, 100, 0]]").unwrap();
+    let colors: HashMap<ObjectID, Color> = config.colors(&diff).into_iter().collect();
+    assert_eq!(colors[&ObjectID(vec!["a".into()])], Color(0, 0, 0));
+    assert_eq!(colors[&ObjectID(vec!["b".into()])], Color(200, 100, 0));
+    assert_eq!(colors[&ObjectID(vec!["c".into()])], config.disappeared);
+    assert_eq!(colors[&ObjectID(vec!["d".into()])], config.appeared);
+    assert_eq!(config.color(1., 2.), Color(100, 50, 0));
+    // Three colors: the middle one is halfway
+    let config = DiffConfig::default();
+    assert_eq!(config.color(0.5, 1.), config.colors[1]);
+    assert_eq!(config.color(5., 1.), config.colors[2]);
+
+    let lines = arrow_lines(
+        na::Point3::origin(),
+        na::Point3::new(4., 0., 0.),
+        &na::Vector3::z(),
+    );
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].1, na::Point3::new(3., -0.5, 0.));
+}
@@ -0,0 +1,699 @@
+//! glTF 2.0 export: frames as instanced meshes, optionally animated across a trajectory.
+//!
+//! Every particle is a node using one of a few shared meshes: a unit sphere, or, for
+//! spherocylinders, a unit cylinder with two sphere caps. Positions, orientations and sizes are
+//! node transforms. With more than one frame, each particle (matched by name, as in the
+//! viewer) and the camera are animated; particles missing from a frame are scaled to zero.
+
+use na;
+
+use serde_json::json;
+use serde_json::Value;
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+
+use camera::{CameraState, Projection, ZFAR, ZNEAR};
+use misc;
+use objects::{Frame, ObjectID};
+use palette::{Color, Palette};
+use parviewer::Config;
+
+/// Rings of latitude and longitude in the sphere mesh
+const STACKS: usize = 12;
+const SLICES: usize = 24;
+
+/// glTF constants
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const LINES: u32 = 1;
+
+/// A camera, and the other settings needed to write frames as a glTF scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfExport {
+    /// Camera projection
+    pub projection: Projection,
+    /// Width of the orthographic view
+    pub extent: f32,
+    /// Image width, in pixels; only the aspect ratio is used
+    pub width: u32,
+    /// Image height, in pixels; only the aspect ratio is used
+    pub height: u32,
+    /// Draw the edges of the unit box
+    pub showbox: bool,
+    /// Frames per second of the animation
+    pub fps: f32,
+}
+
+/// A glTF document, with its binary buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gltf {
+    /// The JSON part, without the buffer's location
+    pub json: Value,
+    /// Geometry and animation data
+    pub buffer: Vec<u8>,
+}
+
+/// Where a particle is at one frame
+#[derive(Debug, Clone, Copy)]
+struct Pose {
+    translation: na::Vector3<f32>,
+    rotation: na::UnitQuaternion<f32>,
+    diameter: f32,
+}
+
+/// The shape of a particle. Spherocylinders keep the ratio of their cylinder length to their
+/// diameter, as in the viewer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Sphere,
+    Spherocylinder(f32),
+}
+
+/// One particle across all frames
+struct Track {
+    names: ObjectID,
+    kind: Kind,
+    color: Color,
+    poses: Vec<Option<Pose>>,
+}
+
+/// Accumulates the buffer, buffer views and accessors of a document
+#[derive(Default)]
+struct Builder {
+    buffer: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Builder {
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        while self.buffer.len() % 4 != 0 {
+            self.buffer.push(0);
+        }
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer.extend_from_slice(bytes);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+
+    /// Add an accessor for `data`, as `size`-component vectors
+    fn floats(&mut self, data: &[f32], size: usize, target: Option<u32>) -> usize {
+        let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_le_bytes().to_vec()).collect();
+        let view = self.view(&bytes, target);
+        let count = data.len() / size;
+        let bound = |pick: fn(f32, f32) -> f32| {
+            (0..size)
+                .map(|i| {
+                    data.iter()
+                        .skip(i)
+                        .step_by(size)
+                        .cloned()
+                        .fold(data[i], pick)
+                })
+                .collect::<Vec<f32>>()
+        };
+        let kind = match size {
+            1 => "SCALAR",
+            3 => "VEC3",
+            4 => "VEC4",
+            _ => panic!("Unexpected accessor size {}", size),
+        };
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": count,
+            "type": kind,
+            "min": bound(f32::min),
+            "max": bound(f32::max),
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn indices(&mut self, data: &[u16]) -> usize {
+        let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_le_bytes().to_vec()).collect();
+        let view = self.view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_SHORT,
+            "count": data.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+}
+
+/// Positions, normals and triangle indices of a sphere of diameter 1
+fn sphere_mesh() -> (Vec<f32>, Vec<f32>, Vec<u16>) {
+    let (mut positions, mut normals, mut indices) = (vec![], vec![], vec![]);
+    for i in 0..=STACKS {
+        let theta = PI * i as f32 / STACKS as f32;
+        for j in 0..=SLICES {
+            let phi = 2. * PI * j as f32 / SLICES as f32;
+            let n = [
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                theta.sin() * phi.sin(),
+            ];
+            normals.extend_from_slice(&n);
+            positions.extend(n.iter().map(|x| x * 0.5));
+        }
+    }
+    for i in 0..STACKS {
+        for j in 0..SLICES {
+            // Counter-clockwise, seen from outside
+            let a = (i * (SLICES + 1) + j) as u16;
+            let b = a + SLICES as u16 + 1;
+            indices.extend_from_slice(&[a, a + 1, b, a + 1, b + 1, b]);
+        }
+    }
+    (positions, normals, indices)
+}
+
+/// Positions, normals and triangle indices of an open cylinder of diameter 1 and height 1,
+/// along y
+fn cylinder_mesh() -> (Vec<f32>, Vec<f32>, Vec<u16>) {
+    let (mut positions, mut normals, mut indices) = (vec![], vec![], vec![]);
+    for j in 0..=SLICES {
+        let phi = 2. * PI * j as f32 / SLICES as f32;
+        let (x, z) = (phi.cos(), phi.sin());
+        for &y in &[-0.5, 0.5] {
+            positions.extend_from_slice(&[x * 0.5, y, z * 0.5]);
+            normals.extend_from_slice(&[x, 0., z]);
+        }
+    }
+    for j in 0..SLICES {
+        let bottom = (2 * j) as u16;
+        let top = bottom + 1;
+        indices.extend_from_slice(&[bottom, top, bottom + 2, bottom + 2, top, top + 2]);
+    }
+    (positions, normals, indices)
+}
+
+/// A palette color, as linear RGBA for a glTF material
+fn linear_rgba(color: Color) -> [f32; 4] {
+    let linear = |c: f32| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = color.to_floats();
+    [linear(r), linear(g), linear(b), 1.]
+}
+
+fn rotation_to(axis: &na::Vector3<f32>) -> na::UnitQuaternion<f32> {
+    na::UnitQuaternion::rotation_between(&na::Vector3::y(), axis).unwrap_or_else(|| {
+        // Antiparallel to y
+        na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), PI)
+    })
+}
+
+fn quaternion(rotation: &na::UnitQuaternion<f32>) -> [f32; 4] {
+    let q = rotation.quaternion();
+    [q.i, q.j, q.k, q.w]
+}
+
+fn triple(v: &na::Vector3<f32>) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+/// Base64, as used in data URIs
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+impl Gltf {
+    /// The document as `.gltf` JSON, with the buffer embedded as a data URI
+    pub fn to_gltf(&self) -> String {
+        let mut json = self.json.clone();
+        json["buffers"] = json!([{
+            "byteLength": self.buffer.len(),
+            "uri": format!("data:application/octet-stream;base64,{}", base64(&self.buffer)),
+        }]);
+        json.to_string()
+    }
+
+    /// The document as binary `.glb`
+    pub fn to_glb(&self) -> Vec<u8> {
+        let mut json = self.json.clone();
+        json["buffers"] = json!([{ "byteLength": self.buffer.len() }]);
+        let mut json = json.to_string().into_bytes();
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+        let mut buffer = self.buffer.clone();
+        while buffer.len() % 4 != 0 {
+            buffer.push(0);
+        }
+
+        let length = 12 + 8 + json.len() + 8 + buffer.len();
+        let mut glb = Vec::with_capacity(length);
+        let word = |glb: &mut Vec<u8>, n: u32| glb.extend_from_slice(&n.to_le_bytes());
+        word(&mut glb, 0x4654_6C67); // "glTF"
+        word(&mut glb, 2);
+        word(&mut glb, length as u32);
+        word(&mut glb, json.len() as u32);
+        word(&mut glb, 0x4E4F_534A); // "JSON"
+        glb.extend_from_slice(&json);
+        word(&mut glb, buffer.len() as u32);
+        word(&mut glb, 0x004E_4942); // "BIN"
+        glb.extend_from_slice(&buffer);
+        glb
+    }
+
+    /// Save to a file: binary if the extension is `.glb`, JSON otherwise.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("glb") => fs::write(path, self.to_glb())?,
+            _ => fs::write(path, self.to_gltf())?,
+        }
+        Ok(())
+    }
+}
+
+impl GltfExport {
+    /// Settings matching a viewer with this config, animated at `fps` frames per second.
+    pub fn new(config: &Config, fps: f32) -> GltfExport {
+        GltfExport {
+            projection: config.projection,
+            extent: config.ortho_extent(),
+            width: config.width,
+            height: config.height,
+            showbox: config.showbox,
+            fps,
+        }
+    }
+
+    /// The camera, as a glTF camera object
+    fn camera(&self, state: &CameraState) -> Value {
+        let aspect = self.width as f32 / self.height.max(1) as f32;
+        match self.projection {
+            Projection::Perspective => json!({
+                "type": "perspective",
+                "perspective": {
+                    "aspectRatio": aspect,
+                    "yfov": state.fov * PI / 180.,
+                    "znear": ZNEAR,
+                    "zfar": ZFAR,
+                },
+            }),
+            Projection::Orthographic => json!({
+                "type": "orthographic",
+                "orthographic": {
+                    "xmag": self.extent / 2.,
+                    "ymag": self.extent / aspect / 2.,
+                    "znear": 0.,
+                    "zfar": ZFAR,
+                },
+            }),
+        }
+    }
+
+    /// Where the camera node goes for a camera position
+    fn camera_pose(&self, state: &CameraState) -> (na::Vector3<f32>, na::UnitQuaternion<f32>) {
+        let pose = state.view_transform().inverse();
+        let translation = match self.projection {
+            Projection::Perspective => pose.translation.vector,
+            // glTF orthographic cameras have no negative near plane, so back away
+            Projection::Orthographic => {
+                let at = state.at().coords;
+                at + (pose.translation.vector - at).normalize() * (ZFAR / 2.)
+            }
+        };
+        (translation, pose.rotation)
+    }
+
+    /// Build a document from frames, each with its camera position. Colors come from the
+    /// palette, as in the viewer. More than one frame makes an animation.
+    pub fn render(&self, frames: &[(CameraState, &Frame)], palette: &mut Palette) -> Gltf {
+        let mut tracks: Vec<Track> = vec![];
+        let mut track_index: HashMap<ObjectID, usize> = HashMap::new();
+        for (i, &(_, frame)) in frames.iter().enumerate() {
+            let spheres = frame.spheres.iter().map(|s| {
+                let pose = Pose {
+                    translation: s.x(),
+                    rotation: na::UnitQuaternion::identity(),
+                    diameter: s.diameter,
+                };
+                (&s.names, Kind::Sphere, pose)
+            });
+            let spherocylinders = frame.spherocylinders.iter().map(|s| {
+                let axis = s.get_axis();
+                let pose = Pose {
+                    translation: s.x(),
+                    rotation: rotation_to(&axis),
+                    diameter: s.diameter,
+                };
+                let ratio = ((axis.norm() - s.diameter) / s.diameter).max(0.);
+                (&s.names, Kind::Spherocylinder(ratio), pose)
+            });
+            for (names, kind, pose) in spheres.chain(spherocylinders) {
+                let n = *track_index.entry(names.clone()).or_insert_with(|| {
+                    tracks.push(Track {
+                        names: names.clone(),
+                        kind,
+                        color: palette.get_color(names),
+                        poses: vec![None; frames.len()],
+                    });
+                    tracks.len() - 1
+                });
+                tracks[n].poses[i] = Some(pose);
+            }
+        }
+
+        let mut builder = Builder::default();
+        let mut materials: Vec<Value> = vec![];
+        let mut meshes: Vec<Value> = vec![];
+        let mut nodes: Vec<Value> = vec![];
+        let mut roots: Vec<usize> = vec![];
+
+        // Shared geometry
+        let mut shapes = vec![];
+        for &(positions, normals, indices) in &[&sphere_mesh(), &cylinder_mesh()] {
+            let positions = builder.floats(positions, 3, Some(ARRAY_BUFFER));
+            let normals = builder.floats(normals, 3, Some(ARRAY_BUFFER));
+            let indices = builder.indices(indices);
+            shapes.push((positions, normals, indices));
+        }
+
+        // One material, and a sphere and a cylinder mesh, per color
+        let mut color_meshes: BTreeMap<Color, (usize, usize)> = BTreeMap::new();
+        for track in &tracks {
+            if color_meshes.contains_key(&track.color) {
+                continue;
+            }
+            let Color(r, g, b) = track.color;
+            materials.push(json!({
+                "name": format!("{:02x}{:02x}{:02x}", r, g, b),
+                "pbrMetallicRoughness": {
+                    "baseColorFactor": linear_rgba(track.color),
+                    "metallicFactor": 0.,
+                    "roughnessFactor": 0.5,
+                },
+            }));
+            let material = materials.len() - 1;
+            for &(positions, normals, indices) in &shapes {
+                meshes.push(json!({
+                    "primitives": [{
+                        "attributes": { "POSITION": positions, "NORMAL": normals },
+                        "indices": indices,
+                        "material": material,
+                    }],
+                }));
+            }
+            let _ = color_meshes.insert(track.color, (meshes.len() - 2, meshes.len() - 1));
+        }
+
+        // Particle nodes, at their first pose
+        let mut track_nodes = vec![];
+        for track in &tracks {
+            let first = track.poses.iter().filter_map(|p| *p).next();
+            let pose = match first {
+                Some(pose) => pose,
+                None => continue,
+            };
+            let visible = track.poses[0].is_some();
+            let d = if visible { pose.diameter } else { 0. };
+            let (sphere_mesh, cylinder_mesh) = color_meshes[&track.color];
+            let mut node = json!({
+                "name": track.names.0.join(" "),
+                "translation": triple(&pose.translation),
+                "scale": [d, d, d],
+            });
+            match track.kind {
+                Kind::Sphere => node["mesh"] = json!(sphere_mesh),
+                Kind::Spherocylinder(ratio) => {
+                    node["rotation"] = json!(quaternion(&pose.rotation));
+                    nodes.push(json!({ "mesh": cylinder_mesh, "scale": [1., ratio, 1.] }));
+                    nodes.push(json!({ "mesh": sphere_mesh, "translation": [0., ratio / 2., 0.] }));
+                    nodes
+                        .push(json!({ "mesh": sphere_mesh, "translation": [0., -ratio / 2., 0.] }));
+                    let n = nodes.len();
+                    node["children"] = json!([n - 3, n - 2, n - 1]);
+                }
+            }
+            nodes.push(node);
+            roots.push(nodes.len() - 1);
+            track_nodes.push((nodes.len() - 1, track));
+        }
+
+        if self.showbox {
+            let positions: Vec<f32> = misc::box_edges()
+                .iter()
+                .flat_map(|&(a, b)| vec![a.x, a.y, a.z, b.x, b.y, b.z])
+                .collect();
+            let positions = builder.floats(&positions, 3, Some(ARRAY_BUFFER));
+            materials.push(json!({
+                "name": "box",
+                "pbrMetallicRoughness": { "baseColorFactor": [1., 0., 0., 1.] },
+            }));
+            meshes.push(json!({
+                "primitives": [{
+                    "attributes": { "POSITION": positions },
+                    "material": materials.len() - 1,
+                    "mode": LINES,
+                }],
+            }));
+            nodes.push(json!({ "name": "box", "mesh": meshes.len() - 1 }));
+            roots.push(nodes.len() - 1);
+        }
+
+        let mut cameras = vec![];
+        let mut camera_node = 0;
+        if let Some((state, _)) = frames.first() {
+            cameras.push(self.camera(state));
+            let (translation, rotation) = self.camera_pose(state);
+            nodes.push(json!({
+                "name": "camera",
+                "camera": 0,
+                "translation": triple(&translation),
+                "rotation": quaternion(&rotation),
+            }));
+            camera_node = nodes.len() - 1;
+            roots.push(camera_node);
+        }
+
+        let mut json = json!({
+            "asset": { "version": "2.0", "generator": "parview" },
+            "scene": 0,
+            "scenes": [{ "nodes": roots }],
+            "nodes": nodes,
+            "meshes": meshes,
+            "materials": materials,
+            "cameras": cameras,
+        });
+
+        if frames.len() > 1 {
+            let mut channels = vec![];
+            let mut samplers = vec![];
+            let times: Vec<f32> = (0..frames.len())
+                .map(|i| i as f32 / self.fps.max(1e-6))
+                .collect();
+            let input = builder.floats(&times, 1, None);
+            let mut animate = |builder: &mut Builder,
+                               node: usize,
+                               path: &str,
+                               data: Vec<f32>,
+                               size: usize,
+                               interpolation: &str| {
+                let output = builder.floats(&data, size, None);
+                samplers.push(json!({
+                    "input": input,
+                    "output": output,
+                    "interpolation": interpolation,
+                }));
+                channels.push(json!({
+                    "sampler": samplers.len() - 1,
+                    "target": { "node": node, "path": path },
+                }));
+            };
+
+            for &(node, track) in &track_nodes {
+                // Missing particles stay where they were last (or first) seen, at zero size
+                let mut last = track.poses.iter().filter_map(|p| *p).next();
+                let (mut translations, mut rotations, mut scales) = (vec![], vec![], vec![]);
+                for pose in &track.poses {
+                    let d = match *pose {
+                        Some(p) => {
+                            last = Some(p);
+                            p.diameter
+                        }
+                        None => 0.,
+                    };
+                    let p = last.expect("Tracks are only made for particles that appear");
+                    translations.extend_from_slice(&triple(&p.translation));
+                    rotations.extend_from_slice(&quaternion(&p.rotation));
+                    scales.extend_from_slice(&[d, d, d]);
+                }
+                animate(&mut builder, node, "translation", translations, 3, "LINEAR");
+                if let Kind::Spherocylinder(_) = track.kind {
+                    animate(&mut builder, node, "rotation", rotations, 4, "LINEAR");
+                }
+                // Sizes change in steps, so particles appear and vanish at once
+                animate(&mut builder, node, "scale", scales, 3, "STEP");
+            }
+
+            let (mut translations, mut rotations) = (vec![], vec![]);
+            for (state, _) in frames {
+                let (translation, rotation) = self.camera_pose(state);
+                translations.extend_from_slice(&triple(&translation));
+                rotations.extend_from_slice(&quaternion(&rotation));
+            }
+            animate(
+                &mut builder,
+                camera_node,
+                "translation",
+                translations,
+                3,
+                "LINEAR",
+            );
+            animate(
+                &mut builder,
+                camera_node,
+                "rotation",
+                rotations,
+                4,
+                "LINEAR",
+            );
+
+            json["animations"] = json!([{
+                "name": "trajectory",
+                "channels": channels,
+                "samplers": samplers,
+            }]);
+        }
+
+        json["bufferViews"] = json!(builder.views);
+        json["accessors"] = json!(builder.accessors);
+        Gltf {
+            json,
+            buffer: builder.buffer,
+        }
+    }
+
+    /// Build a document from frames, each with its camera position, and save it to a file.
+    pub fn save(
+        &self,
+        frames: &[(CameraState, &Frame)],
+        palette: &mut Palette,
+        path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        self.render(frames, palette).save(path)
+    }
+}
+
+#[cfg(test)]
+use objects::{Sphere, Spherocylinder};
+
+#[test]
+fn gltf_animation() {
+    let frame = |x: f32, with_rod: bool| Frame {
+        spheres: vec![Sphere {
+            loc: (x, 0., 0.),
+            diameter: 0.2,
+            names: ObjectID(vec!["A".into()]),
+            vectors: Default::default(),
+        }],
+        spherocylinders: if with_rod {
+            vec![Spherocylinder {
+                loc: (0., 0., 0.),
+                axis: (0., 0., 0.75),
+                diameter: 0.25,
+                names: ObjectID(vec!["B".into()]),
+                vectors: Default::default(),
+            }]
+        } else {
+            vec![]
+        },
+        text: String::new(),
+        time: None,
+        step: None,
+        values: Default::default(),
+        forces: vec![],
+    };
+    let camera = CameraState {
+        yaw: 90.,
+        pitch: 90.,
+        distance: 2.,
+        fov: 45.,
+        target: (0., 0., 0.),
+    };
+    let export = GltfExport {
+        projection: Projection::Perspective,
+        extent: 1.,
+        width: 800,
+        height: 600,
+        showbox: false,
+        fps: 2.,
+    };
+    let (f0, f1) = (frame(0., false), frame(0.5, true));
+    let mut palette = Palette::default();
+    let gltf = export.render(&[(camera, &f0), (camera, &f1)], &mut palette);
+    let json = &gltf.json;
+
+    // Sphere "A", and spherocylinder "B" with its cylinder and caps, and the camera
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 6);
+    let rod = json["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|n| n["name"] == "B")
+        .unwrap();
+    // Not in the first frame, so it starts at zero size
+    assert_eq!(rod["scale"], json!([0., 0., 0.]));
+    let cylinder = &json["nodes"][rod["children"][0].as_u64().unwrap() as usize];
+    assert_eq!(cylinder["scale"], json!([1., 2., 1.]));
+    // Two colors, with a sphere and cylinder mesh each
+    assert_eq!(json["meshes"].as_array().unwrap().len(), 4);
+    let yfov = json["cameras"][0]["perspective"]["yfov"].as_f64().unwrap();
+    assert!((yfov - PI as f64 / 4.).abs() < 1e-6);
+
+    // Two frames, at half a second apart
+    let samplers = json["animations"][0]["samplers"].as_array().unwrap();
+    assert_eq!(samplers.len(), 2 + 3 + 2);
+    let times = &json["accessors"][samplers[0]["input"].as_u64().unwrap() as usize];
+    assert_eq!(times["max"], json!([0.5]));
+
+    // The buffer survives both encodings
+    let glb = gltf.to_glb();
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(glb.len() % 4, 0);
+    assert!(gltf
+        .to_gltf()
+        .contains("data:application/octet-stream;base64,"));
+    assert_eq!(base64(b"parview"), "cGFydmlldw==");
+}
@@ -0,0 +1,91 @@
+//! Hooks, for extending a `Parviewer` without copying its run loop.
+//!
+//! A `Hook` is called at fixed points in each pass through `Parviewer::run_with_hooks`:
+//!
+//! 1. `pre_frame`, just after the window is drawn
+//! 2. `on_index_change`, if the timer has moved to a new frame
+//! 3. `post_frame`, after the objects and overlays are updated
+//! 4. `on_key`, `on_mouse` and `on_action`, for each window event, before the viewer's own
+//!    handling. Returning `EventResult::Consume` stops the viewer from handling it.
+
+use kiss3d::event;
+use kiss3d::event::{Key, Modifiers, WindowEvent};
+
+use keymap::Action;
+use parviewer::Parviewer;
+
+/// Whether a hook has handled an event, or the viewer should go on to handle it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventResult {
+    /// Let the viewer (and later hooks) handle the event
+    Pass,
+    /// The event has been handled, and should go no further
+    Consume,
+}
+
+/// Callbacks into the `Parviewer` run loop. All methods do nothing by default.
+pub trait Hook {
+    /// Called at the start of each tick, before the timer is incremented
+    fn pre_frame(&mut self, _viewer: &mut Parviewer) {}
+
+    /// Called at the end of each tick, after the objects are updated to the current frame
+    fn post_frame(&mut self, _viewer: &mut Parviewer) {}
+
+    /// Called when the timer moves to a new frame, after the objects are updated
+    fn on_index_change(&mut self, _viewer: &mut Parviewer, _index: usize) {}
+
+    /// Called for each key event
+    fn on_key(
+        &mut self,
+        _viewer: &mut Parviewer,
+        _key: Key,
+        _action: event::Action,
+        _modifiers: Modifiers,
+    ) -> EventResult {
+        EventResult::Pass
+    }
+
+    /// Called for each mouse event: cursor movement, buttons and scrolling
+    fn on_mouse(&mut self, _viewer: &mut Parviewer, _event: &WindowEvent) -> EventResult {
+        EventResult::Pass
+    }
+
+    /// Called before the viewer performs an action bound to a key. Custom actions
+    /// (`Action::Custom`) are only handled here.
+    fn on_action(&mut self, _viewer: &mut Parviewer, _action: &Action) -> EventResult {
+        EventResult::Pass
+    }
+}
+
+/// Adapts a closure, as passed to `Parviewer::run`, to a `Hook`.
+pub struct UpdateHook<F> {
+    update: F,
+    new_index: bool,
+}
+
+impl<F> UpdateHook<F>
+where
+    F: FnMut(&mut Parviewer, bool),
+{
+    /// Call `update(viewer, new_index)` after every frame
+    pub fn new(update: F) -> Self {
+        UpdateHook {
+            update,
+            new_index: false,
+        }
+    }
+}
+
+impl<F> Hook for UpdateHook<F>
+where
+    F: FnMut(&mut Parviewer, bool),
+{
+    fn on_index_change(&mut self, _viewer: &mut Parviewer, _index: usize) {
+        self.new_index = true;
+    }
+
+    fn post_frame(&mut self, viewer: &mut Parviewer) {
+        (self.update)(viewer, self.new_index);
+        self.new_index = false;
+    }
+}
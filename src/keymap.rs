@@ -0,0 +1,470 @@
+//! Key bindings: the actions a viewer can take, and which keys trigger them.
+
+use kiss3d::event::{Key, Modifiers};
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+use camera::View;
+
+#[cfg(test)]
+use toml;
+
+/// Something the viewer can do in response to a key press.
+///
+/// In a config file, actions are written in snake case, e.g. `Q = "quit"`, or
+/// `1 = { toggle_partial = 0 }` for actions that take an argument.
+/// Custom actions, like `R = { custom = "record" }`, are handled by a `hooks::Hook`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Do nothing; used to unbind a default key
+    Ignore,
+    /// Close the window
+    Quit,
+    /// Pause or resume playback
+    Pause,
+    /// Speed up playback
+    Faster,
+    /// Slow down playback
+    Slower,
+    /// Reverse the playback direction
+    Reverse,
+    /// Save an image of the window
+    Snapshot,
+    /// Save the current frame as an SVG
+    ExportSvg,
+    /// Print the camera position
+    PrintCamera,
+    /// Look at the target from a standard direction
+    View(View),
+    /// Switch between the perspective and orthographic projections
+    ToggleProjection,
+    /// Toggle whether part `n` (from 0) of the particle names is used for coloring
+    TogglePartial(usize),
+    /// Use all parts of the particle names for coloring
+    AllPartials,
+    /// Use no part of the particle names for coloring
+    NoPartials,
+    /// Move the camera target across the view, by a tenth of the camera distance
+    Pan(Direction),
+    /// Move the camera target to the selected particle, or to the centre of all particles
+    CenterSelection,
+    /// Keep the selected particle (or the centre of all particles) at the view centre
+    ToggleFollow,
+    /// Move the camera target back to its configured position, and stop following
+    ResetTarget,
+    /// Turn the scripted camera path on or off
+    ToggleCameraPath,
+    /// Color particles by how far they have moved from the reference, or by the palette
+    ToggleDiff,
+    /// Draw the next vector of the particles as arrows, or none after the last
+    NextVector,
+    /// Draw the contacts between particles, or stop drawing them
+    ToggleContacts,
+    /// Color particles in the next way, such as by name or by number of contacts
+    NextColoring,
+    /// Show or hide the list of key bindings
+    Help,
+    /// An action handled by a `Hook`, identified by name
+    Custom(String),
+}
+
+/// A direction on the screen
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Towards the left of the window
+    Left,
+    /// Towards the right of the window
+    Right,
+    /// Towards the top of the window
+    Up,
+    /// Towards the bottom of the window
+    Down,
+}
+
+impl Action {
+    /// A short description, for the help overlay
+    pub fn describe(&self) -> String {
+        match *self {
+            Action::Ignore => "Nothing".into(),
+            Action::Quit => "Quit".into(),
+            Action::Pause => "Pause playback".into(),
+            Action::Faster => "Speed up playback".into(),
+            Action::Slower => "Slow down playback".into(),
+            Action::Reverse => "Reverse playback direction".into(),
+            Action::Snapshot => "Save image".into(),
+            Action::ExportSvg => "Save SVG".into(),
+            Action::PrintCamera => "Print camera position".into(),
+            Action::View(View::Isometric) => "Isometric view".into(),
+            Action::View(view) => format!("View from {}", view.name()),
+            Action::ToggleProjection => "Perspective / orthographic".into(),
+            Action::TogglePartial(n) => format!("Toggle coloring by name part {}", n + 1),
+            Action::AllPartials => "Color by all name parts".into(),
+            Action::NoPartials => "Color by no name parts".into(),
+            Action::Pan(direction) => format!("Pan {:?}", direction).to_lowercase(),
+            Action::CenterSelection => "Center on selection".into(),
+            Action::ToggleFollow => "Follow selection on / off".into(),
+            Action::ResetTarget => "Reset camera target".into(),
+            Action::ToggleCameraPath => "Turn the camera path on / off".into(),
+            Action::ToggleDiff => "Displacement coloring on / off".into(),
+            Action::NextVector => "Show the next vector as arrows".into(),
+            Action::ToggleContacts => "Contacts on / off".into(),
+            Action::NextColoring => "Next coloring mode".into(),
+            Action::Help => "Show / hide this help".into(),
+            Action::Custom(ref name) => name.clone(),
+        }
+    }
+}
+
+/// Names for keys, as used in config files. The first name for each key is used for display.
+static KEY_NAMES: &[(&str, Key)] = &[
+    ("A", Key::A),
+    ("B", Key::B),
+    ("C", Key::C),
+    ("D", Key::D),
+    ("E", Key::E),
+    ("F", Key::F),
+    ("G", Key::G),
+    ("H", Key::H),
+    ("I", Key::I),
+    ("J", Key::J),
+    ("K", Key::K),
+    ("L", Key::L),
+    ("M", Key::M),
+    ("N", Key::N),
+    ("O", Key::O),
+    ("P", Key::P),
+    ("Q", Key::Q),
+    ("R", Key::R),
+    ("S", Key::S),
+    ("T", Key::T),
+    ("U", Key::U),
+    ("V", Key::V),
+    ("W", Key::W),
+    ("X", Key::X),
+    ("Y", Key::Y),
+    ("Z", Key::Z),
+    ("0", Key::Key0),
+    ("1", Key::Key1),
+    ("2", Key::Key2),
+    ("3", Key::Key3),
+    ("4", Key::Key4),
+    ("5", Key::Key5),
+    ("6", Key::Key6),
+    ("7", Key::Key7),
+    ("8", Key::Key8),
+    ("9", Key::Key9),
+    ("Key0", Key::Key0),
+    ("Key1", Key::Key1),
+    ("Key2", Key::Key2),
+    ("Key3", Key::Key3),
+    ("Key4", Key::Key4),
+    ("Key5", Key::Key5),
+    ("Key6", Key::Key6),
+    ("Key7", Key::Key7),
+    ("Key8", Key::Key8),
+    ("Key9", Key::Key9),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Space", Key::Space),
+    ("Return", Key::Return),
+    ("Enter", Key::Return),
+    ("Escape", Key::Escape),
+    ("Tab", Key::Tab),
+    ("Backspace", Key::Back),
+    ("Back", Key::Back),
+    (",", Key::Comma),
+    ("Comma", Key::Comma),
+    (".", Key::Period),
+    ("Period", Key::Period),
+    ("/", Key::Slash),
+    ("Slash", Key::Slash),
+    ("-", Key::Minus),
+    ("Minus", Key::Minus),
+    ("=", Key::Equals),
+    ("Equals", Key::Equals),
+    ("[", Key::LBracket),
+    ("LBracket", Key::LBracket),
+    ("]", Key::RBracket),
+    ("RBracket", Key::RBracket),
+    (";", Key::Semicolon),
+    ("Semicolon", Key::Semicolon),
+    ("'", Key::Apostrophe),
+    ("Apostrophe", Key::Apostrophe),
+    ("\\", Key::Backslash),
+    ("Backslash", Key::Backslash),
+    ("`", Key::Grave),
+    ("Grave", Key::Grave),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Insert", Key::Insert),
+    ("Delete", Key::Delete),
+    ("F1", Key::F1),
+    ("F2", Key::F2),
+    ("F3", Key::F3),
+    ("F4", Key::F4),
+    ("F5", Key::F5),
+    ("F6", Key::F6),
+    ("F7", Key::F7),
+    ("F8", Key::F8),
+    ("F9", Key::F9),
+    ("F10", Key::F10),
+    ("F11", Key::F11),
+    ("F12", Key::F12),
+];
+
+/// A key, along with the modifier keys held down with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    /// The key pressed
+    pub key: Key,
+    /// Whether shift is held
+    pub shift: bool,
+    /// Whether control is held
+    pub control: bool,
+    /// Whether alt is held
+    pub alt: bool,
+}
+
+impl Chord {
+    /// A key with no modifiers
+    pub fn key(key: Key) -> Chord {
+        Chord {
+            key,
+            shift: false,
+            control: false,
+            alt: false,
+        }
+    }
+
+    /// A key with the modifiers from a key event
+    pub fn new(key: Key, modifiers: Modifiers) -> Chord {
+        Chord {
+            key,
+            shift: modifiers.contains(Modifiers::Shift),
+            control: modifiers.contains(Modifiers::Control),
+            alt: modifiers.contains(Modifiers::Alt),
+        }
+    }
+
+    /// Parse a name like `Q`, `Shift+/` or `Ctrl+S`. Returns None for unknown keys.
+    pub fn parse(name: &str) -> Option<Chord> {
+        let mut parts: Vec<&str> = name.split('+').collect();
+        // A name ending in "+" is the "+" key itself, which we don't know about
+        let key_name = parts.pop()?;
+        let key = KEY_NAMES
+            .iter()
+            .find(|&&(n, _)| n.eq_ignore_ascii_case(key_name))
+            .map(|&(_, k)| k)?;
+
+        let mut chord = Chord::key(key);
+        for modifier in parts {
+            match &*modifier.to_lowercase() {
+                "shift" => chord.shift = true,
+                "ctrl" | "control" => chord.control = true,
+                "alt" => chord.alt = true,
+                _ => return None,
+            }
+        }
+        Some(chord)
+    }
+
+    /// The name of the chord, as accepted by `parse`
+    pub fn name(&self) -> String {
+        let key_name = KEY_NAMES
+            .iter()
+            .find(|&&(_, k)| k == self.key)
+            .map(|&(n, _)| n.to_string())
+            .unwrap_or_else(|| format!("{:?}", self.key));
+
+        let mut name = String::new();
+        if self.control {
+            name.push_str("Ctrl+");
+        }
+        if self.alt {
+            name.push_str("Alt+");
+        }
+        if self.shift {
+            name.push_str("Shift+");
+        }
+        name.push_str(&key_name);
+        name
+    }
+
+    fn has_modifiers(&self) -> bool {
+        self.shift || self.control || self.alt
+    }
+}
+
+/// A mapping from keys to actions
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Chord, Action)>,
+}
+
+impl Keymap {
+    /// A keymap with no bindings
+    pub fn empty() -> Keymap {
+        Keymap { bindings: vec![] }
+    }
+
+    /// Bind a key to an action, replacing any previous binding of that key. Binding a key to
+    /// `Action::Ignore` unbinds it, including the fallback to its unmodified binding.
+    pub fn bind(&mut self, chord: Chord, action: Action) {
+        self.bindings.retain(|&(c, _)| c != chord);
+        self.bindings.push((chord, action));
+    }
+
+    /// Bind keys by name, as in the `[keys]` section of a config file.
+    pub fn bind_names(&mut self, names: &BTreeMap<String, Action>) -> Result<(), String> {
+        for (name, action) in names {
+            let chord = Chord::parse(name).ok_or_else(|| format!("Unknown key '{}'", name))?;
+            self.bind(chord, action.clone());
+        }
+        Ok(())
+    }
+
+    /// Find the action for a key press. A binding without modifiers also matches the key
+    /// pressed with modifiers, unless there is a more specific binding. Keys bound to
+    /// `Action::Ignore` have no action.
+    pub fn get(&self, chord: &Chord) -> Option<&Action> {
+        let exact = self.bindings.iter().find(|&&(c, _)| c == *chord);
+        exact
+            .or_else(|| {
+                self.bindings
+                    .iter()
+                    .find(|&&(c, _)| c.key == chord.key && !c.has_modifiers())
+            })
+            .map(|&(_, ref action)| action)
+            .filter(|&action| *action != Action::Ignore)
+    }
+
+    /// All bindings, in the order they were added, including those to `Action::Ignore`
+    pub fn bindings(&self) -> &[(Chord, Action)] {
+        &self.bindings
+    }
+
+    /// Lines of text describing each binding, for the help overlay
+    pub fn help(&self) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|&&(_, ref action)| *action != Action::Ignore)
+            .map(|&(ref chord, ref action)| format!("{:>8}  {}", chord.name(), action.describe()))
+            .collect()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Keymap::empty();
+        let defaults = vec![
+            (Key::Q, Action::Quit),
+            (Key::Space, Action::Pause),
+            (Key::Comma, Action::Slower),
+            (Key::Period, Action::Faster),
+            (Key::F, Action::Reverse),
+            (Key::S, Action::Snapshot),
+            (Key::E, Action::ExportSvg),
+            (Key::W, Action::PrintCamera),
+            (Key::X, Action::View(View::PlusX)),
+            (Key::Y, Action::View(View::PlusY)),
+            (Key::Z, Action::View(View::PlusZ)),
+            (Key::V, Action::View(View::Isometric)),
+            (Key::O, Action::ToggleProjection),
+            (Key::Key1, Action::TogglePartial(0)),
+            (Key::Key2, Action::TogglePartial(1)),
+            (Key::Key3, Action::TogglePartial(2)),
+            (Key::Key4, Action::TogglePartial(3)),
+            (Key::Key5, Action::TogglePartial(4)),
+            (Key::Key6, Action::TogglePartial(5)),
+            (Key::Key7, Action::TogglePartial(6)),
+            (Key::Key8, Action::TogglePartial(7)),
+            (Key::Key9, Action::AllPartials),
+            (Key::Key0, Action::NoPartials),
+            (Key::J, Action::Pan(Direction::Left)),
+            (Key::L, Action::Pan(Direction::Right)),
+            (Key::I, Action::Pan(Direction::Up)),
+            (Key::K, Action::Pan(Direction::Down)),
+            (Key::C, Action::CenterSelection),
+            (Key::T, Action::ToggleFollow),
+            (Key::Home, Action::ResetTarget),
+            (Key::P, Action::ToggleCameraPath),
+            (Key::D, Action::ToggleDiff),
+            (Key::A, Action::NextVector),
+            (Key::N, Action::ToggleContacts),
+            (Key::G, Action::NextColoring),
+            (Key::H, Action::Help),
+        ];
+        for (key, action) in defaults {
+            keymap.bind(Chord::key(key), action);
+        }
+        // Shift looks from the other side
+        let opposites = vec![
+            (Key::X, View::MinusX),
+            (Key::Y, View::MinusY),
+            (Key::Z, View::MinusZ),
+        ];
+        for (key, view) in opposites {
+            let mut chord = Chord::key(key);
+            chord.shift = true;
+            keymap.bind(chord, Action::View(view));
+        }
+        let mut question_mark = Chord::key(Key::Slash);
+        question_mark.shift = true;
+        keymap.bind(question_mark, Action::Help);
+        keymap
+    }
+}
+
+#[test]
+fn keymap_parse_names() {
+    assert_eq!(Chord::parse("q"), Some(Chord::key(Key::Q)));
+    assert_eq!(Chord::parse("."), Some(Chord::key(Key::Period)));
+    let chord = Chord::parse("Ctrl+Shift+S").unwrap();
+    assert_eq!(chord.key, Key::S);
+    assert!(chord.shift && chord.control && !chord.alt);
+    assert_eq!(chord.name(), "Ctrl+Shift+S");
+    assert_eq!(Chord::parse("Hyper+S"), None);
+    assert_eq!(Chord::parse("NotAKey"), None);
+}
+
+#[test]
+fn keymap_overrides() {
+    let s = "
+        Q = \"ignore\"
+        \"Shift+S\" = \"ignore\"
+        X = \"quit\"
+        \"Shift+1\" = { toggle_partial = 3 }
+        A = { pan = \"left\" }
+    ";
+    let names: BTreeMap<String, Action> = toml::from_str(s).unwrap();
+    let mut keymap = Keymap::default();
+    keymap.bind_names(&names).unwrap();
+
+    assert_eq!(keymap.get(&Chord::key(Key::Q)), None);
+    assert_eq!(keymap.get(&Chord::key(Key::X)), Some(&Action::Quit));
+    let pan_left = Action::Pan(Direction::Left);
+    assert_eq!(keymap.get(&Chord::key(Key::A)), Some(&pan_left));
+    let mut shift_1 = Chord::key(Key::Key1);
+    assert_eq!(keymap.get(&shift_1), Some(&Action::TogglePartial(0)));
+    shift_1.shift = true;
+    assert_eq!(keymap.get(&shift_1), Some(&Action::TogglePartial(3)));
+    // Unmodified bindings also match with modifiers
+    let mut ctrl_s = Chord::key(Key::S);
+    ctrl_s.control = true;
+    assert_eq!(keymap.get(&ctrl_s), Some(&Action::Snapshot));
+    // ... unless that chord is ignored
+    let mut shift_s = Chord::key(Key::S);
+    shift_s.shift = true;
+    assert_eq!(keymap.get(&shift_s), None);
+    // ... but exact matches come first
+    let mut shift_x = Chord::key(Key::X);
+    shift_x.shift = true;
+    assert_eq!(keymap.get(&shift_x), Some(&Action::View(View::MinusX)));
+}
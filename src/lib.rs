@@ -11,6 +11,8 @@
 #![deny(unused_results)]
 
 extern crate flate2;
+extern crate gif;
+extern crate image;
 extern crate rand;
 extern crate serde;
 extern crate serde_json;
@@ -19,20 +21,59 @@ extern crate toml;
 extern crate kiss3d;
 extern crate nalgebra as na;
 
+#[cfg(feature = "mpeg")]
+extern crate mpeg_encoder;
+
 //use na::{Indexable,Iterable};
 
 pub use serde::{Deserialize, Serialize};
 //use std::io;
 
+pub mod analysis;
+pub mod camera;
+pub mod clusters;
+pub mod coloring;
 pub mod config;
+pub mod contacts;
+pub mod diff;
+pub mod gltf;
+pub mod hooks;
+pub mod keymap;
 pub mod misc;
 pub mod objects;
+pub mod overlays;
 pub mod palette;
 pub mod parviewer;
+pub mod plot;
+pub mod pov;
+pub mod progress;
+pub mod record;
+pub mod snapshot;
+pub mod style;
+pub mod svg;
 pub mod timer;
+pub mod vectors;
+pub mod video;
+pub mod viewports;
 
+pub use analysis::AnalysisConfig;
+pub use clusters::{find_clusters, ClusterColors, ClusterConfig};
+pub use coloring::{ColorMode, ColoringConfig};
 pub use config::TomlConfig;
-pub use objects::{Frame, ObjectID, ObjectTracker, Sphere, EPSILON};
+pub use contacts::{find_contacts, Contact, ContactConfig};
+pub use diff::DiffConfig;
+pub use gltf::GltfExport;
+pub use hooks::{EventResult, Hook};
+pub use keymap::{Action, Keymap};
+pub use objects::{Arrow, Bond, Frame, ObjectID, ObjectTracker, SimBox, Sphere, EPSILON};
 pub use palette::{Color, Palette};
 pub use parviewer::{Config, Parviewer};
+pub use pov::PovExport;
+pub use progress::Progress;
+pub use record::RecordConfig;
+pub use style::TextStyle;
+pub use svg::SvgExport;
 pub use timer::Timer;
+pub use vectors::VectorConfig;
+pub use video::{VideoConfig, VideoEncoder, VideoFormat};
+pub use viewports::ViewportConfig;
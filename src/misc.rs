@@ -60,6 +60,7 @@ pub fn generate_frame(path: &Path) -> Result<Vec<objects::Frame>, Error> {
                 loc: (loc.x, loc.y, loc.z),
                 diameter: s * 0.2,
                 names,
+                vectors: Default::default(),
             }
         })
         .collect();
@@ -68,6 +69,10 @@ pub fn generate_frame(path: &Path) -> Result<Vec<objects::Frame>, Error> {
         spheres,
         spherocylinders: vec![],
         text: String::new(),
+        time: None,
+        step: None,
+        values: Default::default(),
+        forces: vec![],
     };
 
     let mut framevec: Vec<objects::Frame> = vec![];
@@ -84,17 +89,26 @@ pub fn generate_frame(path: &Path) -> Result<Vec<objects::Frame>, Error> {
                         newr = random::<f32>() * 0.2f32;
                     };
                     let (sx, sy, sz) = s.loc;
-                    let v = na::Vector3::new(sx, sy, sz) + (rand_vec() * 0.1f32);
+                    let step = rand_vec() * 0.1f32;
+                    let v = na::Vector3::new(sx, sy, sz) + step;
+                    // Frames are 0.1 time units apart
+                    let velocity = step / 0.1;
+                    let vectors = vec![("velocity".into(), (velocity.x, velocity.y, velocity.z))];
                     objects::Sphere {
                         loc: (v[0], v[1], v[2]),
                         diameter: newr,
                         names: s.names.clone(),
+                        vectors: vectors.into_iter().collect(),
                     }
                 })
                 .collect(),
 
             spherocylinders: vec![],
             text: format!("Frame {} with {} spheres", i, f.spheres.len()),
+            time: Some(i as f32 * 0.1),
+            step: Some(i as u64 * 100),
+            values: Default::default(),
+            forces: vec![],
         };
 
         if i > 10 && i < 20 {
@@ -164,13 +178,158 @@ pub fn draw_cube(window: &mut Window) -> kiss3d::scene::SceneNode {
     cube
 }
 
-/// Turn an error into a print message.
+/// Radius of the edges of the cube drawn by `draw_cube`
+pub const BOX_EDGE_RADIUS: f32 = 0.01;
+
+/// The ends of the 12 edges of the cube drawn by `draw_cube`, for drawing it elsewhere.
+pub fn box_edges() -> Vec<(na::Point3<f32>, na::Point3<f32>)> {
+    let mut edges = vec![];
+    for axis in 0..3 {
+        for &(u, v) in &[(-0.5, -0.5), (-0.5, 0.5), (0.5, -0.5), (0.5, 0.5)] {
+            // The other two coordinates are u and v; the edge runs along `axis`
+            let mut a = na::Point3::origin();
+            a[(axis + 1) % 3] = u;
+            a[(axis + 2) % 3] = v;
+            a[axis] = -0.5;
+            let mut b = a;
+            b[axis] = 0.5;
+            edges.push((a, b));
+        }
+    }
+    edges
+}
+
+/// A value that can be substituted into a template by `format_template`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue {
+    /// A number, formatted as an integer if it has no fractional part and no precision is given
+    Number(f64),
+    /// A string, substituted as-is
+    Text(String),
+}
+
+/// Fill in a template such as `"t={time:.3} φ={phi:.4}"`, looking up each name with `lookup`.
+///
+/// A field is `{name}` or `{name:spec}`, where `spec` is `[0][width][.precision]` as in
+/// `format!`. `{{` and `}}` are literal braces. Names that `lookup` does not know become `?`.
+pub fn format_template<F>(template: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<TemplateValue>,
+{
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                let _ = chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                let _ = chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let field: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let (name, spec) = match field.find(':') {
+                    Some(i) => (&field[..i], &field[i + 1..]),
+                    None => (&field[..], ""),
+                };
+                out.push_str(&format_field(lookup(name.trim()), spec));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Format a single template field, according to a spec like `04` or `8.3`.
+fn format_field(value: Option<TemplateValue>, spec: &str) -> String {
+    let zero = spec.starts_with('0');
+    let (width, precision) = match spec.find('.') {
+        Some(i) => (&spec[..i], spec[i + 1..].parse::<usize>().ok()),
+        None => (spec, None),
+    };
+    let width = width.parse::<usize>().unwrap_or(0);
+
+    match value {
+        None => format!("{:>w$}", "?", w = width),
+        Some(TemplateValue::Text(s)) => format!("{:w$}", s, w = width),
+        Some(TemplateValue::Number(v)) => match precision {
+            Some(p) if zero => format!("{:0w$.p$}", v, w = width, p = p),
+            Some(p) => format!("{:w$.p$}", v, w = width, p = p),
+            None if v.fract() == 0. && v.abs() < 1e15 && zero => {
+                format!("{:0w$}", v as i64, w = width)
+            }
+            None if v.fract() == 0. && v.abs() < 1e15 => format!("{:w$}", v as i64, w = width),
+            None if zero => format!("{:0w$}", v, w = width),
+            None => format!("{:w$}", v, w = width),
+        },
+    }
+}
+
+/// Size of the window, in the logical pixels used by cursor positions and planar drawing
+pub fn logical_size(window: &Window) -> (f32, f32) {
+    let hidpi = window.hidpi_factor() as f32;
+    (
+        window.width() as f32 / hidpi,
+        window.height() as f32 / hidpi,
+    )
+}
+
+/// Convert coordinates in the window frame (0 to 1, from the top left) to the coordinates used
+/// by `Window::draw_planar_line` (logical pixels, from the centre, y up).
+pub fn planar_point(window: &Window, x: f32, y: f32) -> na::Point2<f32> {
+    let (width, height) = logical_size(window);
+    na::Point2::new((x - 0.5) * width, (0.5 - y) * height)
+}
+
+/// Convert a cursor position (logical pixels, from the top left) to the window frame (0 to 1).
+pub fn window_fraction(window: &Window, cursor: (f64, f64)) -> (f32, f32) {
+    let (width, height) = logical_size(window);
+    (cursor.0 as f32 / width, cursor.1 as f32 / height)
+}
+
+/// A short name for a trajectory file, for labels: the file name, without its directory.
+pub fn file_label(fname: &str) -> String {
+    Path::new(fname).file_name().map_or_else(
+        || fname.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Turn an error into a message on stderr.
 pub fn err_print(err: &dyn std::error::Error) {
-    println!("Description: {}", err);
-    println!("Debug version: {:?}", err);
+    eprintln!("Description: {}", err);
+    eprintln!("Debug version: {:?}", err);
 
     if let Some(e) = err.source() {
-        println!("Cause.");
+        eprintln!("Cause.");
         err_print(e);
     }
 }
+
+/// Print an error to stderr, and exit with a failing status, for the `main` of a binary.
+pub fn exit_with_error(err: &dyn std::error::Error) -> ! {
+    eprintln!("ERROR.");
+    err_print(err);
+    std::process::exit(1)
+}
+
+#[test]
+fn template_fields() {
+    let lookup = |name: &str| match name {
+        "time" => Some(TemplateValue::Number(1.23456)),
+        "step" => Some(TemplateValue::Number(1200.)),
+        "text" => Some(TemplateValue::Text("hello".into())),
+        _ => None,
+    };
+    assert_eq!(
+        format_template("t={time:.3} n={step:06} {text}!", lookup),
+        "t=1.235 n=001200 hello!"
+    );
+    assert_eq!(format_template("{{time}} {phi:.2}", lookup), "{time} ?");
+    assert_eq!(
+        format_template("{time:8.2}|{step}", lookup),
+        "    1.23|1200"
+    );
+}
@@ -7,12 +7,17 @@ use kiss3d::scene::SceneNode;
 use kiss3d::window::Window;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::From;
+use std::f32::consts::PI;
 use std::iter::FromIterator;
+use std::mem;
 
-use palette::Palette;
+use contacts::ContactForce;
+use misc::TemplateValue;
+use palette::{Color, Palette};
 
 /// A minimal value that is close enough to 0 for visual purposes
 pub const EPSILON: f32 = 1e-6;
@@ -49,6 +54,12 @@ impl ObjectID {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Returns true if this object is `prefix`, or in the group named by `prefix`; that is, if
+    /// its names start with all of the names in `prefix`. An empty prefix matches everything.
+    pub fn starts_with(&self, prefix: &ObjectID) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
 }
 
 #[derive(Deserialize, Clone)]
@@ -60,6 +71,14 @@ struct MinimalFrame {
     pub spherocylinders: Option<Vec<Spherocylinder>>,
     /// Text to display
     pub text: Option<String>,
+    /// Simulation time of the frame
+    pub time: Option<f32>,
+    /// Simulation step number
+    pub step: Option<u64>,
+    /// Named scalars, such as energy or packing fraction
+    pub values: Option<BTreeMap<String, f64>>,
+    /// Forces between pairs of particles
+    pub forces: Option<Vec<ContactForce>>,
 }
 
 #[derive(Serialize, Clone)]
@@ -71,6 +90,18 @@ pub struct Frame {
     pub spherocylinders: Vec<Spherocylinder>,
     /// Text to display
     pub text: String,
+    /// Simulation time of the frame, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<f32>,
+    /// Simulation step number, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<u64>,
+    /// Named scalars, such as energy or packing fraction
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, f64>,
+    /// Forces between pairs of particles, such as contact forces from the simulation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub forces: Vec<ContactForce>,
 }
 
 impl<'de> Deserialize<'de> for Frame {
@@ -80,10 +111,240 @@ impl<'de> Deserialize<'de> for Frame {
             spheres: minim.spheres.unwrap_or(vec![]),
             spherocylinders: minim.spherocylinders.unwrap_or(vec![]),
             text: minim.text.unwrap_or(String::new()),
+            time: minim.time,
+            step: minim.step,
+            values: minim.values.unwrap_or_default(),
+            forces: minim.forces.unwrap_or_default(),
         })
     }
 }
 
+impl Frame {
+    /// Look up a piece of metadata by name: `time`, `step`, `text`, or a key of `values`.
+    pub fn lookup(&self, name: &str) -> Option<TemplateValue> {
+        match name {
+            "time" => self.time.map(|t| TemplateValue::Number(t as f64)),
+            "step" => self.step.map(|n| TemplateValue::Number(n as f64)),
+            "text" => Some(TemplateValue::Text(self.text.clone())),
+            _ => self.values.get(name).map(|&v| TemplateValue::Number(v)),
+        }
+    }
+
+    /// The mean position of the objects whose names start with `prefix`, or None if there are
+    /// no such objects.
+    pub fn centre(&self, prefix: &ObjectID) -> Option<na::Point3<f32>> {
+        let sphere_locs = self
+            .spheres
+            .iter()
+            .filter(|s| s.names.starts_with(prefix))
+            .map(|s| s.x());
+        let spherocylinder_locs = self
+            .spherocylinders
+            .iter()
+            .filter(|s| s.names.starts_with(prefix))
+            .map(|s| s.x());
+        let (sum, n) = sphere_locs
+            .chain(spherocylinder_locs)
+            .fold((na::Vector3::zeros(), 0), |(sum, n), x| (sum + x, n + 1));
+        if n == 0 {
+            return None;
+        }
+        Some(na::Point3::from(sum / n as f32))
+    }
+
+    /// The nearest object hit by a ray from `origin` along `dir`, as from
+    /// `camera::ViewCamera::ray`.
+    pub fn pick(&self, origin: &na::Point3<f32>, dir: &na::Vector3<f32>) -> Option<&ObjectID> {
+        let dir = dir.normalize();
+        let spheres = self.spheres.iter().map(|s| {
+            let x = na::Point3::from(s.x());
+            (
+                &s.names,
+                ray_distance(origin, &dir, &x, &x, s.diameter / 2.),
+            )
+        });
+        let spherocylinders = self.spherocylinders.iter().map(|s| {
+            let x = na::Point3::from(s.x());
+            let axis = s.get_axis();
+            let length = axis.norm();
+            // The axis runs from cap to cap; the core segment is shorter by a diameter
+            let half = if length > s.diameter {
+                axis * ((length - s.diameter) / (2. * length))
+            } else {
+                na::Vector3::zeros()
+            };
+            let d = ray_distance(origin, &dir, &(x - half), &(x + half), s.diameter / 2.);
+            (&s.names, d)
+        });
+        spheres
+            .chain(spherocylinders)
+            .filter_map(|(names, d)| d.map(|d| (names, d)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+            .map(|(names, _)| names)
+    }
+
+    /// The names of the vectors carried by any of the objects, in order
+    pub fn vector_names(&self) -> Vec<String> {
+        let names: BTreeSet<&String> = self
+            .spheres
+            .iter()
+            .flat_map(|s| s.vectors.keys())
+            .chain(self.spherocylinders.iter().flat_map(|s| s.vectors.keys()))
+            .collect();
+        names.into_iter().cloned().collect()
+    }
+
+    /// A frame part of the way (`fraction`, from 0 to 1) from this one to `next`, for smooth
+    /// motion between frames. Objects are matched by name; those missing from `next` stay where
+    /// they are, and those only in `next` do not appear yet. The text and values are this
+    /// frame's, and the time is interpolated.
+    pub fn interpolate(&self, next: &Frame, fraction: f32) -> Frame {
+        let lerp = |a: f32, b: f32| a + (b - a) * fraction;
+        let lerp3 = |(ax, ay, az): (f32, f32, f32), (bx, by, bz): (f32, f32, f32)| {
+            (lerp(ax, bx), lerp(ay, by), lerp(az, bz))
+        };
+        // Vectors missing from the next frame stay as they are
+        let lerp_vectors = |a: &BTreeMap<String, (f32, f32, f32)>,
+                            b: &BTreeMap<String, (f32, f32, f32)>| {
+            a.iter()
+                .map(|(name, &v)| match b.get(name) {
+                    Some(&w) => (name.clone(), lerp3(v, w)),
+                    None => (name.clone(), v),
+                })
+                .collect()
+        };
+
+        let next_spheres: HashMap<&ObjectID, &Sphere> =
+            next.spheres.iter().map(|s| (&s.names, s)).collect();
+        let spheres = self
+            .spheres
+            .iter()
+            .map(|s| match next_spheres.get(&s.names) {
+                Some(n) => Sphere {
+                    loc: lerp3(s.loc, n.loc),
+                    diameter: lerp(s.diameter, n.diameter),
+                    names: s.names.clone(),
+                    vectors: lerp_vectors(&s.vectors, &n.vectors),
+                },
+                None => s.clone(),
+            })
+            .collect();
+
+        let next_spherocylinders: HashMap<&ObjectID, &Spherocylinder> =
+            next.spherocylinders.iter().map(|s| (&s.names, s)).collect();
+        let spherocylinders = self
+            .spherocylinders
+            .iter()
+            .map(|s| match next_spherocylinders.get(&s.names) {
+                Some(n) => {
+                    let (a, mut b) = (s.get_axis(), n.get_axis());
+                    // The two ends are the same, so turn through the smaller angle
+                    if a.dot(&b) < 0. {
+                        b = -b;
+                    }
+                    // Turn the axis and change its length separately, so it does not shrink
+                    // as it turns. The length and diameter change in proportion.
+                    let length = lerp(a.norm(), b.norm());
+                    let direction = a.normalize() * (1. - fraction) + b.normalize() * fraction;
+                    let axis = if direction.norm() > EPSILON {
+                        direction.normalize() * length
+                    } else {
+                        a
+                    };
+                    Spherocylinder {
+                        loc: lerp3(s.loc, n.loc),
+                        axis: (axis.x, axis.y, axis.z),
+                        diameter: lerp(s.diameter, n.diameter),
+                        names: s.names.clone(),
+                        vectors: lerp_vectors(&s.vectors, &n.vectors),
+                    }
+                }
+                None => s.clone(),
+            })
+            .collect();
+
+        Frame {
+            spheres,
+            spherocylinders,
+            text: self.text.clone(),
+            time: match (self.time, next.time) {
+                (Some(a), Some(b)) => Some(lerp(a, b)),
+                (time, _) => time,
+            },
+            step: self.step,
+            values: self.values.clone(),
+            forces: self.forces.clone(),
+        }
+    }
+}
+
+/// If a ray (with `dir` normalized) passes within `radius` of the segment from `a` to `b`, the
+/// distance along the ray to its closest approach.
+fn ray_distance(
+    origin: &na::Point3<f32>,
+    dir: &na::Vector3<f32>,
+    a: &na::Point3<f32>,
+    b: &na::Point3<f32>,
+    radius: f32,
+) -> Option<f32> {
+    let u = b - a;
+    let w = a - origin;
+    let (uu, ud) = (u.dot(&u), u.dot(dir));
+    let denom = uu - ud * ud;
+    // Position along the segment (0 to 1) of the closest approach
+    let s = if denom > EPSILON {
+        ((ud * dir.dot(&w) - u.dot(&w)) / denom).clamp(0., 1.)
+    } else {
+        0.
+    };
+    let t = (w + u * s).dot(dir);
+    let miss = (w + u * s - dir * t).norm();
+    if t > 0. && miss <= radius {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// A box around the particles, centred on the origin. A periodic box wraps around, so that
+/// particles near opposite faces are neighbours.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct SimBox {
+    /// Lengths of the sides
+    pub size: (f32, f32, f32),
+    /// Whether the box wraps around
+    #[serde(default)]
+    pub periodic: bool,
+}
+
+impl SimBox {
+    /// get the lengths of the sides as a Vector3
+    pub fn lengths(&self) -> na::Vector3<f32> {
+        let (x, y, z) = self.size;
+        na::Vector3::new(x, y, z)
+    }
+
+    /// Volume of the box
+    pub fn volume(&self) -> f32 {
+        let (x, y, z) = self.size;
+        x * y * z
+    }
+
+    /// The shortest vector from `a` to `b`: through the walls, for a periodic box
+    pub fn separation(&self, a: &na::Point3<f32>, b: &na::Point3<f32>) -> na::Vector3<f32> {
+        let mut d = b - a;
+        if self.periodic {
+            let lengths = self.lengths();
+            for i in 0..3 {
+                if lengths[i] > EPSILON {
+                    d[i] -= lengths[i] * (d[i] / lengths[i]).round();
+                }
+            }
+        }
+        d
+    }
+}
+
 /// An object that will be drawable by Parview.
 ///
 /// This is the trait-based interface so that Parview can manage it.
@@ -105,6 +366,23 @@ pub enum ObjectEnum {
     Sphere(Sphere),
     /// a spherocylinder
     Spherocylinder(Spherocylinder),
+    /// an arrow, showing a vector
+    Arrow(Arrow),
+    /// a bond between two points, such as a contact
+    Bond(Bond),
+}
+
+impl ObjectEnum {
+    /// Color a node for this object: from the palette, or with an arrow's or bond's own color
+    fn set_color(&self, node: &mut SceneNode, palette: &mut Palette) {
+        let color = match *self {
+            ObjectEnum::Arrow(ref a) => a.color,
+            ObjectEnum::Bond(ref b) => b.color,
+            _ => return palette.set_color(self.id(), node),
+        };
+        let (r, g, b) = color.to_floats();
+        node.set_color(r, g, b);
+    }
 }
 
 impl Object for ObjectEnum {
@@ -112,6 +390,8 @@ impl Object for ObjectEnum {
         match *self {
             ObjectEnum::Sphere(ref s) => s.id(),
             ObjectEnum::Spherocylinder(ref s) => s.id(),
+            ObjectEnum::Arrow(ref a) => a.id(),
+            ObjectEnum::Bond(ref b) => b.id(),
         }
     }
 
@@ -119,6 +399,8 @@ impl Object for ObjectEnum {
         match *self {
             ObjectEnum::Sphere(ref s) => s.new_node(window),
             ObjectEnum::Spherocylinder(ref s) => s.new_node(window),
+            ObjectEnum::Arrow(ref a) => a.new_node(window),
+            ObjectEnum::Bond(ref b) => b.new_node(window),
         }
     }
 
@@ -130,6 +412,10 @@ impl Object for ObjectEnum {
                 s.update(o, nodes)
             }
             (&mut ObjectEnum::Spherocylinder(_), _) => unimplemented!(),
+            (&mut ObjectEnum::Arrow(ref mut a), &ObjectEnum::Arrow(ref o)) => a.update(o, nodes),
+            (&mut ObjectEnum::Arrow(_), _) => unimplemented!(),
+            (&mut ObjectEnum::Bond(ref mut b), &ObjectEnum::Bond(ref o)) => b.update(o, nodes),
+            (&mut ObjectEnum::Bond(_), _) => unimplemented!(),
         }
     }
 }
@@ -138,6 +424,9 @@ impl Object for ObjectEnum {
 pub struct ObjectTracker {
     /// The set of objects
     objects: HashMap<ObjectID, (ObjectEnum, SceneNode)>,
+    /// Arrows, bonds and other objects drawn along with the particles. Their IDs are built from
+    /// particle names, so they are kept apart from the particles.
+    extras: HashMap<ObjectID, (ObjectEnum, SceneNode)>,
     /// The scene to which to attach new objects
     parent: SceneNode,
 }
@@ -154,18 +443,50 @@ impl From<Spherocylinder> for ObjectEnum {
     }
 }
 
+impl From<Arrow> for ObjectEnum {
+    fn from(a: Arrow) -> ObjectEnum {
+        ObjectEnum::Arrow(a)
+    }
+}
+
+impl From<Bond> for ObjectEnum {
+    fn from(b: Bond) -> ObjectEnum {
+        ObjectEnum::Bond(b)
+    }
+}
+
 impl ObjectTracker {
     /// Create a new `ObjectTracker` associated with a given `Window`.
     pub fn new(window: &mut Window) -> ObjectTracker {
         ObjectTracker {
             objects: HashMap::new(),
+            extras: HashMap::new(),
             parent: window.add_group(),
         }
     }
 
+    /// The group holding the objects, for showing or hiding them all at once
+    pub fn group(&self) -> SceneNode {
+        self.parent.clone()
+    }
+
+    /// Color an object, instead of the palette color, until the next update
+    pub fn set_color(&mut self, name: &ObjectID, color: Color) {
+        if let Some(&mut (_, ref mut node)) = self.objects.get_mut(name) {
+            let (r, g, b) = color.to_floats();
+            node.set_color(r, g, b);
+        }
+    }
+
     /// The meat of `ObjectTracker`. Update old objects and the scene to match
     /// new objects
     pub fn update(&mut self, frame: &Frame, palette: &mut Palette) {
+        self.update_with(frame, vec![], palette)
+    }
+
+    /// Update old objects and the scene to match the objects of a frame, along with some
+    /// others, such as arrows and bonds, which keep their own colors.
+    pub fn update_with(&mut self, frame: &Frame, extras: Vec<ObjectEnum>, palette: &mut Palette) {
         // TODO: this used to be &ObjectID, which is probably faster
         let mut seen: HashSet<ObjectID> =
             FromIterator::from_iter(self.objects.keys().map(|ref k| (*k).clone()));
@@ -187,7 +508,7 @@ impl ObjectTracker {
                 Entry::Occupied(mut entry) => {
                     let &mut (ref mut obj, ref mut node) = entry.get_mut();
                     obj.update(&ObjectEnum::from(new_object.clone()), node);
-                    palette.set_color(obj.id(), node);
+                    obj.set_color(node, palette);
                     //let is_invisible = node.data().is_root();
                     // if is_invisible {
                     //     self.parent.add_child(node);
@@ -196,7 +517,7 @@ impl ObjectTracker {
                 }
                 Entry::Vacant(v) => {
                     let mut node = new_object.new_node(&mut self.parent);
-                    palette.set_color(new_object.id(), &mut node);
+                    new_object.set_color(&mut node, palette);
                     let _ = v.insert((ObjectEnum::from(new_object.clone()), node));
                 }
             }
@@ -208,6 +529,42 @@ impl ObjectTracker {
             });
             let _ = self.objects.remove(&k);
         }
+
+        self.update_extras(extras, palette);
+    }
+
+    /// Update the arrows, bonds and other extras to match `extras`
+    fn update_extras(&mut self, extras: Vec<ObjectEnum>, palette: &mut Palette) {
+        let mut seen: HashSet<ObjectID> = self.extras.keys().cloned().collect();
+        for new_object in extras {
+            let name = new_object.id().clone();
+            let _ = seen.remove(&name);
+            match self.extras.entry(name) {
+                Entry::Occupied(mut entry) => {
+                    let &mut (ref mut obj, ref mut node) = entry.get_mut();
+                    if mem::discriminant(obj) == mem::discriminant(&new_object) {
+                        obj.update(&new_object, node);
+                    } else {
+                        // Another kind of object with the same name: start again
+                        node.unlink();
+                        *node = new_object.new_node(&mut self.parent);
+                        *obj = new_object;
+                    }
+                    obj.set_color(node, palette);
+                }
+                Entry::Vacant(v) => {
+                    let mut node = new_object.new_node(&mut self.parent);
+                    new_object.set_color(&mut node, palette);
+                    let _ = v.insert((new_object, node));
+                }
+            }
+        }
+
+        for k in seen {
+            if let Some((_, mut node)) = self.extras.remove(&k) {
+                node.unlink();
+            }
+        }
     }
 }
 
@@ -220,6 +577,9 @@ pub struct Sphere {
     pub diameter: f32,
     /// Color. if none, one will be assigned
     pub names: ObjectID,
+    /// Named vectors, such as `velocity` or `force`, drawn as arrows from the centre
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vectors: BTreeMap<String, (f32, f32, f32)>,
 }
 
 impl Sphere {
@@ -266,6 +626,9 @@ pub struct Spherocylinder {
     pub diameter: f32,
     /// Color. if none, one will be assigned
     pub names: ObjectID,
+    /// Named vectors, such as `velocity` or `force`, drawn as arrows from the centre
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vectors: BTreeMap<String, (f32, f32, f32)>,
 }
 
 impl Spherocylinder {
@@ -340,3 +703,246 @@ impl Object for Spherocylinder {
         }
     }
 }
+
+/// The rotation turning the y axis, along which kiss3d builds cylinders and cones, to point
+/// along `vector`
+fn rotation_from_y(vector: &na::Vector3<f32>) -> na::UnitQuaternion<f32> {
+    na::UnitQuaternion::rotation_between(&na::Vector3::y(), vector).unwrap_or_else(|| {
+        // Pointing straight down, or with no length
+        if vector.y < 0. {
+            na::UnitQuaternion::from_axis_angle(&na::Vector3::x_axis(), PI)
+        } else {
+            na::UnitQuaternion::identity()
+        }
+    })
+}
+
+/// Fraction of the length of an arrow taken by its head
+const ARROW_HEAD: f32 = 0.25;
+
+/// An arrow from a point, drawn as a cylinder with a cone at the tip; for showing vectors,
+/// such as velocities or forces.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Arrow {
+    /// Where the arrow starts
+    pub loc: (f32, f32, f32),
+    /// From the start of the arrow to its tip
+    pub vector: (f32, f32, f32),
+    /// Diameter of the shaft; the head is twice as wide
+    pub width: f32,
+    /// Color, instead of one from the palette
+    pub color: Color,
+    /// Names, unique among all objects
+    pub names: ObjectID,
+}
+
+impl Arrow {
+    /// get the location as a Vector3
+    pub fn x(&self) -> na::Vector3<f32> {
+        let (x, y, z) = self.loc;
+        na::Vector3::new(x, y, z)
+    }
+
+    /// get the vector as a Vector3
+    pub fn get_vector(&self) -> na::Vector3<f32> {
+        let (x, y, z) = self.vector;
+        na::Vector3::new(x, y, z)
+    }
+
+    /// Move and turn a node from `new_node` to match the arrow, and size its shaft and head.
+    fn place(&self, node: &mut SceneNode) {
+        let vector = self.get_vector();
+        node.set_local_translation(self.x().into());
+        node.set_local_rotation(rotation_from_y(&vector));
+
+        // A group's scale does not move its children, so the shaft and head are sized and
+        // placed along the y axis themselves
+        let length = vector.norm().max(EPSILON);
+        let (shaft, head) = (length * (1. - ARROW_HEAD), length * ARROW_HEAD);
+        let width = self.width;
+        let mut part = 0;
+        node.apply_to_scene_nodes_mut(&mut |n: &mut SceneNode| {
+            // The group itself comes first, then its children in the order they were added
+            match part {
+                1 => {
+                    n.set_local_scale(width, shaft, width);
+                    n.set_local_translation(na::Translation3::new(0., shaft / 2., 0.));
+                }
+                2 => {
+                    n.set_local_scale(width, head, width);
+                    n.set_local_translation(na::Translation3::new(0., shaft + head / 2., 0.));
+                }
+                _ => {}
+            }
+            part += 1;
+        });
+    }
+}
+
+impl Object for Arrow {
+    fn id(&self) -> &ObjectID {
+        &self.names
+    }
+
+    fn new_node(&self, parent: &mut SceneNode) -> SceneNode {
+        let mut node = parent.add_group();
+        // Of length and width 1, along y; `place` sizes them
+        let _ = node.add_cylinder(0.5, 1.);
+        let _ = node.add_cone(1., 1.);
+        self.place(&mut node);
+        node
+    }
+
+    fn update(&mut self, other: &Self, node: &mut SceneNode) {
+        let moved = self.loc != other.loc || self.vector != other.vector;
+        if moved || (self.width - other.width).abs() > EPSILON {
+            self.loc = other.loc;
+            self.vector = other.vector;
+            self.width = other.width;
+            self.place(node);
+        }
+        // Colored by `ObjectTracker`
+        self.color = other.color;
+    }
+}
+
+/// A cylinder from one point to another, such as a contact between two particles
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Bond {
+    /// One end
+    pub from: (f32, f32, f32),
+    /// The other end
+    pub to: (f32, f32, f32),
+    /// Diameter
+    pub width: f32,
+    /// Color, instead of one from the palette
+    pub color: Color,
+    /// Names, unique among all objects
+    pub names: ObjectID,
+}
+
+impl Bond {
+    /// Move, turn and size a cylinder from `new_node` to match the bond.
+    fn place(&self, node: &mut SceneNode) {
+        let v = |(x, y, z): (f32, f32, f32)| na::Vector3::new(x, y, z);
+        let (from, to) = (v(self.from), v(self.to));
+        let length = (to - from).norm().max(EPSILON);
+        node.set_local_scale(self.width, length, self.width);
+        node.set_local_translation(((from + to) / 2.).into());
+        node.set_local_rotation(rotation_from_y(&(to - from)));
+    }
+}
+
+impl Object for Bond {
+    fn id(&self) -> &ObjectID {
+        &self.names
+    }
+
+    fn new_node(&self, parent: &mut SceneNode) -> SceneNode {
+        // Of length and width 1, along y; `place` sizes it
+        let mut node = parent.add_cylinder(0.5, 1.);
+        self.place(&mut node);
+        node
+    }
+
+    fn update(&mut self, other: &Self, node: &mut SceneNode) {
+        let moved = self.from != other.from || self.to != other.to;
+        if moved || (self.width - other.width).abs() > EPSILON {
+            self.from = other.from;
+            self.to = other.to;
+            self.width = other.width;
+            self.place(node);
+        }
+        // Colored by `ObjectTracker`
+        self.color = other.color;
+    }
+}
+
+#[test]
+fn frame_centre_and_pick() {
+    let sphere = |x: f32, names: &[&str]| Sphere {
+        loc: (x, 0., 0.),
+        diameter: 0.2,
+        names: ObjectID(names.iter().map(|&n| n.to_string()).collect()),
+        vectors: BTreeMap::new(),
+    };
+    let frame = Frame {
+        spheres: vec![
+            sphere(0., &["A", "1"]),
+            sphere(1., &["A", "2"]),
+            sphere(3., &["B", "1"]),
+        ],
+        spherocylinders: vec![],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![],
+    };
+
+    let group = ObjectID(vec!["A".into()]);
+    assert_eq!(frame.centre(&group), Some(na::Point3::new(0.5, 0., 0.)));
+    assert_eq!(frame.centre(&ObjectID(vec![])).unwrap().x, 4. / 3.);
+    assert_eq!(frame.centre(&ObjectID(vec!["C".into()])), None);
+
+    // Looking along -x from x = 10, the sphere at x = 3 is in front
+    let origin = na::Point3::new(10., 0.05, 0.);
+    let dir = na::Vector3::new(-1., 0., 0.);
+    assert_eq!(frame.pick(&origin, &dir).unwrap().0, vec!["B", "1"]);
+    let origin = na::Point3::new(1., 5., 0.);
+    let dir = na::Vector3::new(0., -2., 0.);
+    assert_eq!(frame.pick(&origin, &dir).unwrap().0, vec!["A", "2"]);
+    assert_eq!(frame.pick(&origin, &-dir), None);
+}
+
+#[test]
+fn frame_interpolate() {
+    let names = |n: &str| ObjectID(vec![n.into()]);
+    let frame = |x: f32, axis: (f32, f32, f32), time: f32| Frame {
+        spheres: vec![
+            Sphere {
+                loc: (x, 0., 0.),
+                diameter: 0.2,
+                names: names("A"),
+                vectors: vec![("velocity".into(), (4. * x, 0., 0.))]
+                    .into_iter()
+                    .collect(),
+            },
+            Sphere {
+                loc: (x, 1., 0.),
+                diameter: 0.2,
+                names: names(if x > 0. { "C" } else { "B" }),
+                vectors: BTreeMap::new(),
+            },
+        ],
+        spherocylinders: vec![Spherocylinder {
+            loc: (0., 0., x),
+            axis,
+            diameter: 0.1,
+            names: names("R"),
+            vectors: BTreeMap::new(),
+        }],
+        text: format!("x={}", x),
+        time: Some(time),
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![],
+    };
+    let (a, b) = (frame(0., (1., 0., 0.), 1.), frame(1., (-0.6, -0.8, 0.), 2.));
+    let mid = a.interpolate(&b, 0.25);
+
+    assert_eq!(mid.spheres.len(), 2);
+    assert_eq!(mid.spheres[0].loc, (0.25, 0., 0.));
+    assert_eq!(mid.spheres[0].vectors["velocity"], (1., 0., 0.));
+    // B is not in the next frame, so it stays put, and C has not appeared yet
+    assert_eq!(mid.spheres[1].loc, (0., 1., 0.));
+    assert_eq!(mid.spheres[1].names, names("B"));
+    assert_eq!(mid.time, Some(1.25));
+    assert_eq!(mid.text, "x=0");
+
+    // The ends are the same, so the axis turns the short way, towards +y, keeping its length
+    let axis = mid.spherocylinders[0].get_axis();
+    assert_eq!(mid.spherocylinders[0].loc, (0., 0., 0.25));
+    assert!((axis.norm() - 1.).abs() < EPSILON);
+    assert!((axis.y / axis.x - 2. / 9.).abs() < EPSILON);
+}
@@ -0,0 +1,274 @@
+//! Small guides drawn over the scene: an axis triad, a scale bar, and the nematic director.
+
+use na;
+
+use kiss3d::camera::Camera;
+use kiss3d::window::Window;
+use serde::{Deserialize, Serialize};
+
+use analysis::NematicOrder;
+use misc;
+use palette::Color;
+
+/// Configuration for the axis triad
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct TriadConfig {
+    /// Centre of the triad, in the window frame (0 to 1)
+    pub position: (f32, f32),
+    /// Length of each axis, as a fraction of the window height
+    pub size: f32,
+    /// Colors of the x, y and z axes
+    pub colors: (Color, Color, Color),
+    /// Font size of the axis labels
+    pub font_size: f32,
+}
+
+impl Default for TriadConfig {
+    fn default() -> Self {
+        TriadConfig {
+            position: (0.08, 0.88),
+            size: 0.06,
+            colors: (Color(230, 60, 60), Color(60, 200, 60), Color(80, 120, 255)),
+            font_size: 24.,
+        }
+    }
+}
+
+/// Configuration for the scale bar
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct ScaleBarConfig {
+    /// Left end of the bar, in the window frame (0 to 1)
+    pub position: (f32, f32),
+    /// Longest the bar may be, as a fraction of the window width
+    pub length: f32,
+    /// Units shown after the length, e.g. "σ" or "nm"
+    pub units: String,
+    /// Color of the bar and its label
+    pub color: Color,
+    /// Font size of the label
+    pub font_size: f32,
+}
+
+impl Default for ScaleBarConfig {
+    fn default() -> Self {
+        ScaleBarConfig {
+            position: (0.75, 0.92),
+            length: 0.2,
+            units: String::new(),
+            color: Color(255, 255, 255),
+            font_size: 24.,
+        }
+    }
+}
+
+/// Configuration for the nematic director indicator
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct DirectorConfig {
+    /// Centre of the indicator, in the window frame (0 to 1)
+    pub position: (f32, f32),
+    /// Half the length of the director line, as a fraction of the window height
+    pub size: f32,
+    /// Color of the line and its label
+    pub color: Color,
+    /// Font size of the label
+    pub font_size: f32,
+}
+
+impl Default for DirectorConfig {
+    fn default() -> Self {
+        DirectorConfig {
+            position: (0.92, 0.12),
+            size: 0.06,
+            color: Color(255, 255, 255),
+            font_size: 24.,
+        }
+    }
+}
+
+/// Draw a line between two points in the window frame
+fn draw_line(window: &mut Window, a: (f32, f32), b: (f32, f32), color: Color) {
+    let pa = misc::planar_point(window, a.0, a.1);
+    let pb = misc::planar_point(window, b.0, b.1);
+    window.draw_planar_line(&pa, &pb, &color.to_point3());
+}
+
+/// The x, y and z axes, rotated with the camera.
+pub struct Triad {
+    config: TriadConfig,
+}
+
+impl Triad {
+    /// Create a triad from its configuration
+    pub fn new(config: TriadConfig) -> Triad {
+        Triad { config }
+    }
+
+    /// Draw the axes, and return their labels as (text, x, y, color), with positions in the
+    /// window frame.
+    pub fn draw(&self, window: &mut Window, camera: &dyn Camera) -> Vec<(String, f32, f32, Color)> {
+        let (width, height) = misc::logical_size(window);
+        let (cx, cy) = self.config.position;
+        let length = self.config.size * height;
+        let rotation = camera.view_transform().rotation;
+        let (x_color, y_color, z_color) = self.config.colors;
+        let axes = [
+            ("x", na::Vector3::x(), x_color),
+            ("y", na::Vector3::y(), y_color),
+            ("z", na::Vector3::z(), z_color),
+        ];
+
+        let mut labels = vec![];
+        for &(name, axis, color) in &axes {
+            // In camera coordinates, x is to the right and y is up
+            let v = rotation * axis;
+            let tip = |scale: f32| {
+                (
+                    cx + v.x * length * scale / width,
+                    cy - v.y * length * scale / height,
+                )
+            };
+            draw_line(window, (cx, cy), tip(1.), color);
+            let (lx, ly) = tip(1.25);
+            labels.push((name.to_string(), lx, ly, color));
+        }
+        labels
+    }
+
+    /// Font size for the labels
+    pub fn font_size(&self) -> f32 {
+        self.config.font_size
+    }
+}
+
+/// The nematic director of the spherocylinders, as a line rotated with the camera like the
+/// triad, labelled with the order parameter.
+pub struct Director {
+    config: DirectorConfig,
+}
+
+impl Director {
+    /// Create a director indicator from its configuration
+    pub fn new(config: DirectorConfig) -> Director {
+        Director { config }
+    }
+
+    /// Draw the director, and return its label as (text, x, y, color), with the position in
+    /// the window frame.
+    pub fn draw(
+        &self,
+        window: &mut Window,
+        camera: &dyn Camera,
+        nematic: &NematicOrder,
+    ) -> (String, f32, f32, Color) {
+        let (width, height) = misc::logical_size(window);
+        let (cx, cy) = self.config.position;
+        let length = self.config.size * height;
+        let v = camera.view_transform().rotation * nematic.director;
+        // The sign of the director has no meaning, so the line goes both ways from the centre
+        let end = |scale: f32| {
+            (
+                cx + v.x * length * scale / width,
+                cy - v.y * length * scale / height,
+            )
+        };
+        let color = self.config.color;
+        draw_line(window, end(-1.), end(1.), color);
+        let text = format!("S = {:.2}", nematic.order);
+        (text, cx - 0.04, cy + self.config.size + 0.05, color)
+    }
+
+    /// Font size for the label
+    pub fn font_size(&self) -> f32 {
+        self.config.font_size
+    }
+}
+
+/// The longest "round" length (1, 2 or 5 times a power of 10) no longer than `max`
+pub fn round_length(max: f32) -> f32 {
+    if !max.is_finite() || max <= 0. {
+        return 0.;
+    }
+    let exponent = max.log10().floor() as i32;
+    // Divide for negative exponents, so that e.g. 0.05 is exact
+    let scaled = |m: f32| {
+        if exponent < 0 {
+            m / 10f32.powi(-exponent)
+        } else {
+            m * 10f32.powi(exponent)
+        }
+    };
+    for &m in &[5., 2., 1.] {
+        if scaled(m) <= max * (1. + 1e-5) {
+            return scaled(m);
+        }
+    }
+    scaled(1.)
+}
+
+/// A bar of a round length in simulation units, at the depth of the camera target.
+pub struct ScaleBar {
+    config: ScaleBarConfig,
+}
+
+impl ScaleBar {
+    /// Create a scale bar from its configuration
+    pub fn new(config: ScaleBarConfig) -> ScaleBar {
+        ScaleBar { config }
+    }
+
+    /// Draw the bar, and return its label as (text, x, y, color), with the position in the
+    /// window frame.
+    pub fn draw(
+        &self,
+        window: &mut Window,
+        camera: &dyn Camera,
+        target: &na::Point3<f32>,
+    ) -> Option<(String, f32, f32, Color)> {
+        let (width, height) = misc::logical_size(window);
+        let size = na::Vector2::new(width, height);
+
+        // Window length of one unit, across the screen at the target
+        let right = camera.view_transform().rotation.inverse() * na::Vector3::x();
+        let a = camera.project(target, &size);
+        let b = camera.project(&(*target + right), &size);
+        let unit = (b - a).norm();
+        if !unit.is_finite() || unit <= 0. {
+            return None;
+        }
+
+        let length = round_length(self.config.length * width / unit);
+        let (x, y) = self.config.position;
+        let end = x + length * unit / width;
+        let color = self.config.color;
+        draw_line(window, (x, y), (end, y), color);
+        let tick = 0.01;
+        draw_line(window, (x, y - tick), (x, y + tick), color);
+        draw_line(window, (end, y - tick), (end, y + tick), color);
+
+        let text = if self.config.units.is_empty() {
+            format!("{}", length)
+        } else {
+            format!("{} {}", length, self.config.units)
+        };
+        Some((text, x, y - 0.05, color))
+    }
+
+    /// Font size for the label
+    pub fn font_size(&self) -> f32 {
+        self.config.font_size
+    }
+}
+
+#[test]
+fn scale_bar_round_length() {
+    assert_eq!(round_length(1.), 1.);
+    assert_eq!(round_length(7.3), 5.);
+    assert_eq!(round_length(3.), 2.);
+    assert_eq!(round_length(0.15), 0.1);
+    assert_eq!(round_length(260.), 200.);
+    assert_eq!(format!("{}", round_length(0.06)), "0.05");
+    assert_eq!(round_length(0.), 0.);
+}
@@ -54,6 +54,40 @@ impl Color {
     }
 }
 
+/// The color for `value` on a scale from 0 to `max`, running through `colors`, evenly spaced.
+/// Values outside the scale get the color at its nearest end.
+pub fn colormap(colors: &[Color], value: f32, max: f32) -> Color {
+    let stops = colors.len();
+    if stops == 0 {
+        return Color(255, 255, 255);
+    }
+    if stops == 1 || max <= 0. {
+        return colors[0];
+    }
+    let position = (value / max).clamp(0., 1.) * (stops - 1) as f32;
+    let i = (position as usize).min(stops - 2);
+    let fraction = position - i as f32;
+    let (Color(r0, g0, b0), Color(r1, g1, b1)) = (colors[i], colors[i + 1]);
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    Color(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+}
+
+/// The color `fraction` of the way around a cyclic scale through `colors`, evenly spaced,
+/// which goes from the last color back to the first. Fractions wrap around, so 0 and 1 are the
+/// same color.
+pub fn cyclic_colormap(colors: &[Color], fraction: f32) -> Color {
+    let stops = colors.len();
+    if stops == 0 {
+        return Color(255, 255, 255);
+    }
+    let position = fraction.rem_euclid(1.) * stops as f32;
+    let i = (position as usize).min(stops - 1);
+    let fraction = position - i as f32;
+    let (Color(r0, g0, b0), Color(r1, g1, b1)) = (colors[i], colors[(i + 1) % stops]);
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    Color(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+}
+
 /// A [bool] for keeping track of which part of the objectID should be used
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Serialize, Deserialize)]
 pub struct PartialIDer {
@@ -6,19 +6,44 @@ use kiss3d;
 use na;
 
 use flate2::read::GzDecoder;
-use kiss3d::event::{Action, Key, WindowEvent};
+use image::{imageops, DynamicImage, RgbImage};
+use kiss3d::camera::Camera;
+use kiss3d::event;
+use kiss3d::event::{MouseButton, WindowEvent};
 use kiss3d::window::Window;
 use serde::{Deserialize, Serialize};
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::f32::consts::PI;
+use std::fs;
 use std::fs::File;
-use std::path::Path;
+use std::iter;
+use std::path::{Path, PathBuf};
 
+use analysis::{self, AnalysisConfig};
+use camera::{CameraPath, CameraState, Projection, Tile, ViewCamera, ZFAR, ZNEAR};
+use clusters::{find_clusters, ClusterColors, ClusterConfig, ClusterStats};
+use coloring::{ColorMode, ColoringConfig};
+use contacts::{find_contacts, ContactConfig};
+use diff::{self, DiffConfig, FrameDiff};
+use hooks::{EventResult, Hook, UpdateHook};
+use keymap::{Action, Chord, Direction, Keymap};
 use misc;
-use objects::{Frame, ObjectTracker};
+use misc::TemplateValue;
+use objects::EPSILON;
+use objects::{Frame, ObjectEnum, ObjectID, ObjectTracker, SimBox};
+use overlays::{Director, DirectorConfig, ScaleBar, ScaleBarConfig, Triad, TriadConfig};
 use palette::{Color, Palette};
+use plot::{Plot, PlotConfig};
+use snapshot;
+use snapshot::SnapshotConfig;
+use style::{LightMode, TextStyle};
+use svg::SvgExport;
 use timer::Timer;
+use vectors::{self, VectorConfig};
+use viewports::{Rect, Viewport, ViewportConfig};
 
 /// The configuration options for a Parviewer instance.
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,18 +54,112 @@ pub struct Config {
     pub yaw: f32,
     /// Field-of-view range of the camera, in degrees
     pub fov: f32,
-    /// Distance of the camera from the origin
+    /// Distance of the camera from the target
     pub distance: f32,
+    /// Camera projection
+    pub projection: Projection,
+    /// Width of the orthographic view at the initial distance. None matches the perspective view.
+    pub extent: Option<f32>,
+    /// Point the camera looks at
+    pub target: (f32, f32, f32),
+    /// Names (or a prefix of names) of the particles to keep at the view centre
+    pub follow: Option<ObjectID>,
     /// Window width
     pub width: u32,
     /// Window height
     pub height: u32,
     /// How long to pause before looping. None indicates no looping.
     pub pauseloop: Option<f32>,
+    /// First frame of the playback range
+    pub start: usize,
+    /// End of the playback range (exclusive). None plays to the end.
+    pub end: Option<usize>,
+    /// Show only every `stride`-th frame
+    pub stride: usize,
+    /// Reverse direction at the ends of the playback range, instead of wrapping around
+    pub pingpong: bool,
     /// framerate limit
     pub framerate: f32,
+    /// Playback speeds (frames per second); their reciprocals are added as slower speeds
+    pub speeds: Vec<f32>,
+    /// Simulation time units per second of playback, for real-time playback. None plays
+    /// frame-by-frame.
+    pub timescale: Option<f32>,
+    /// Move particles smoothly between frames, instead of jumping from one to the next
+    pub interpolate: bool,
     /// Show Box
     pub showbox: bool,
+    /// Background color
+    pub background: Color,
+    /// Where the scene is lit from
+    pub light: LightMode,
+    /// Template for the frame text (see `misc::format_template`). None shows the frame's text.
+    pub text_format: Option<String>,
+    /// A plot of per-frame values, drawn in a corner of the window
+    pub plot: Option<PlotConfig>,
+    /// An axis triad, rotating with the camera
+    pub triad: Option<TriadConfig>,
+    /// A scale bar in simulation length units
+    pub scale_bar: Option<ScaleBarConfig>,
+    /// The nematic director of the spherocylinders and their order parameter
+    pub director: Option<DirectorConfig>,
+    /// Key bindings, overriding the defaults
+    pub keys: BTreeMap<String, Action>,
+    /// Scripted camera keyframes
+    pub camera_path: CameraPath,
+    /// Font, size, color and position of the frame text
+    pub text: TextStyle,
+    /// Size, background and file names of snapshots
+    pub snapshot: SnapshotConfig,
+    /// Layout of the viewports, when comparing trajectories
+    pub viewports: ViewportConfig,
+    /// How vectors of the particles are drawn as arrows
+    pub vectors: VectorConfig,
+    /// How contacts between particles are found and drawn
+    pub contacts: ContactConfig,
+    /// How clusters of touching particles are found and colored
+    pub clusters: ClusterConfig,
+    /// The box around the particles, for packing fractions, and for finding neighbours
+    /// through the walls if it is periodic
+    pub sim_box: Option<SimBox>,
+    /// Statistics added to the values of each frame, such as the packing fraction
+    pub analysis: Option<AnalysisConfig>,
+    /// Coloring by properties of the particles, instead of by name
+    pub coloring: ColoringConfig,
+    /// Displacement coloring, on from the start. None starts with it off, comparing with
+    /// the first frame when turned on.
+    pub diff: Option<DiffConfig>,
+}
+
+impl Config {
+    /// Width of the orthographic view: `extent` if set, or else the width of the perspective
+    /// view at the target.
+    pub fn ortho_extent(&self) -> f32 {
+        self.extent
+            .unwrap_or(2. * self.distance * (self.fov * PI / 360.).tan())
+    }
+
+    /// The camera position when the viewer starts
+    pub fn initial_camera(&self) -> CameraState {
+        CameraState {
+            yaw: self.yaw,
+            pitch: self.pitch,
+            distance: self.distance,
+            fov: self.fov,
+            target: self.target,
+        }
+    }
+
+    /// The camera position at a frame, following the camera path and `follow` as the viewer
+    /// does, for rendering frames without a window.
+    pub fn camera_at(&self, index: usize, frame: &Frame) -> CameraState {
+        let mut state = self.initial_camera();
+        self.camera_path.apply(index as f32, &mut state);
+        if let Some(centre) = self.follow.as_ref().and_then(|f| frame.centre(f)) {
+            state.target = (centre.x, centre.y, centre.z);
+        }
+        state
+    }
 }
 
 /// Open a `json` or `json.gz` file, and deserialize it into a `Vec<Frame>`
@@ -69,6 +188,80 @@ pub fn open_file(path: &Path) -> Result<Vec<Frame>, Box<dyn Error>> {
     Ok(coded)
 }
 
+/// Set the window background to a color
+fn set_background(window: &mut Window, color: Color) {
+    let Color(r, g, b) = color;
+    window.set_background_color(r as f32 / 255., g as f32 / 255., b as f32 / 255.);
+}
+
+/// Frame `ix` of `frames`, or part of the way to the `next` frame. Positions past the end of
+/// the trajectory show its last frame.
+fn frame_at(frames: &[Frame], ix: usize, next: Option<(usize, f32)>) -> Cow<'_, Frame> {
+    let last = frames.len().saturating_sub(1);
+    let ix = ix.min(last);
+    match next {
+        Some((next, fraction)) if next <= last => {
+            Cow::Owned(frames[ix].interpolate(&frames[next], fraction))
+        }
+        _ => Cow::Borrowed(&frames[ix]),
+    }
+}
+
+/// The arrows for the vector called `vector_field`, and the contacts, drawn with a frame,
+/// along with the number of contacts if they are drawn
+fn glyphs(
+    frame: &Frame,
+    vector_field: Option<&str>,
+    vectors: &VectorConfig,
+    contacts: Option<&ContactConfig>,
+    sim_box: Option<&SimBox>,
+) -> (Vec<ObjectEnum>, Option<usize>) {
+    let arrows = match vector_field {
+        Some(name) => vectors.arrows(frame, name),
+        None => vec![],
+    };
+    let (bonds, count) = match contacts {
+        Some(config) => {
+            let contacts = find_contacts(frame, config.tolerance, sim_box);
+            (config.bonds(&contacts), Some(contacts.len()))
+        }
+        None => (vec![], None),
+    };
+    let arrows = arrows.into_iter().map(ObjectEnum::from);
+    let objects = arrows
+        .chain(bonds.into_iter().map(ObjectEnum::from))
+        .collect();
+    (objects, count)
+}
+
+/// A frame colored by cluster: only the particles of clusters large enough to show, their
+/// colors, and statistics of those clusters
+fn cluster_frame<'a>(
+    frame: Cow<'a, Frame>,
+    config: &ClusterConfig,
+    cluster_colors: &mut ClusterColors,
+    sim_box: Option<&SimBox>,
+) -> (Cow<'a, Frame>, Vec<(ObjectID, Color)>, ClusterStats) {
+    let clusters = find_clusters(&frame, config.tolerance, sim_box);
+    let shown = config.shown(&clusters);
+    let colors = cluster_colors.assign(config, shown);
+    let stats = ClusterStats::new(shown);
+    if shown.len() == clusters.len() {
+        (frame, colors, stats)
+    } else {
+        (Cow::Owned(config.filter(&frame, shown)), colors, stats)
+    }
+}
+
+/// Another trajectory, compared with the main one in its own viewport
+struct Trajectory {
+    label: String,
+    frames: Vec<Frame>,
+    palette: Palette,
+    nodes: ObjectTracker,
+    cluster_colors: ClusterColors,
+}
+
 /// The main entry point,maintaining a window, a Config, objects, etc.
 pub struct Parviewer {
     config: Config,
@@ -80,9 +273,61 @@ pub struct Parviewer {
     /// kiss3d Window
     pub window: Window,
     /// Camera
-    pub camera: kiss3d::camera::ArcBall,
+    pub camera: ViewCamera,
+    /// Field-of-view of the camera, in degrees
+    fov: f32,
+    /// Follow the scripted camera path, if there is one
+    pub follow_camera_path: bool,
+    /// The selected particle or group, as a prefix of names. None selects everything.
+    pub selection: Option<ObjectID>,
+    /// Keep the selection at the view centre
+    pub following: bool,
+    /// Move particles smoothly between frames
+    pub interpolate: bool,
+    /// The vector of each particle drawn as an arrow, if any
+    pub vector_field: Option<String>,
+    vectors: VectorConfig,
+    /// Draw the contacts between particles
+    pub show_contacts: bool,
+    /// The number of contacts in the current frame, when drawing them
+    pub contact_count: Option<usize>,
+    contacts: ContactConfig,
+    clusters: ClusterConfig,
+    cluster_colors: ClusterColors,
+    /// Statistics of the clusters shown in the current frame, when coloring by cluster
+    pub cluster_stats: Option<ClusterStats>,
+    /// What particles are colored by, when not showing displacements. Coloring by cluster
+    /// hides clusters smaller than the configured size.
+    pub color_mode: ColorMode,
+    coloring: ColoringConfig,
+    analysis: AnalysisConfig,
+    /// Color particles by how far they have moved from the reference
+    pub show_diff: bool,
+    diff: DiffConfig,
+    /// A trajectory to compare each frame with, frame by frame
+    reference: Option<Vec<Frame>>,
+    /// How the shown frame differs from its reference, when comparing them
+    frame_diff: Option<FrameDiff>,
     nodes: ObjectTracker,
+    /// Trajectories shown beside this one, following the same timer
+    others: Vec<Trajectory>,
+    /// Name of the trajectory, shown in its viewport when comparing trajectories
+    pub label: String,
     font: std::rc::Rc<kiss3d::text::Font>,
+    plot: Option<Plot>,
+    triad: Option<Triad>,
+    scale_bar: Option<ScaleBar>,
+    director: Option<Director>,
+    /// Last known cursor position, in logical pixels
+    cursor: (f64, f64),
+    /// Whether the mouse is being dragged across the plot
+    plot_drag: bool,
+    /// Key bindings
+    pub keymap: Keymap,
+    /// Show the list of key bindings
+    pub show_help: bool,
+    /// Save a snapshot after the next render
+    pub snapshot_requested: bool,
 
     /// Do not increment timer when paused
     pub paused: bool,
@@ -91,7 +336,7 @@ pub struct Parviewer {
 impl Parviewer {
     /// Create a new Parviewer instance from a give Config
     pub fn new(
-        frames: Vec<Frame>,
+        mut frames: Vec<Frame>,
         palette: Palette,
         config: Config,
     ) -> Result<Parviewer, Box<dyn Error>> {
@@ -105,21 +350,28 @@ impl Parviewer {
             let _ = misc::draw_cube(&mut window);
         }
 
-        let eye = na::Point3::new(0.0f32, 0.0, config.distance);
-        let at = na::Point3::origin();
-        let mut camera = kiss3d::camera::ArcBall::new_with_frustrum(
+        let (x, y, z) = config.target;
+        let at = na::Point3::new(x, y, z);
+        let eye = at + na::Vector3::new(0.0f32, 0.0, config.distance);
+        let mut arcball = kiss3d::camera::ArcBall::new_with_frustrum(
             config.fov * PI / 180.,
-            0.1,
-            1024.0,
+            ZNEAR,
+            ZFAR,
             eye,
             at,
         );
 
-        camera.set_yaw(config.yaw * PI / 180.);
-        camera.set_pitch(config.pitch * PI / 180.);
+        arcball.set_yaw(config.yaw * PI / 180.);
+        arcball.set_pitch(config.pitch * PI / 180.);
+        let camera = ViewCamera::new(
+            arcball,
+            config.projection,
+            config.ortho_extent(),
+            config.fov * PI / 180.,
+        );
 
-        // window.set_background_color(1.0, 1.0, 1.0);
-        window.set_light(kiss3d::light::Light::StickToCamera);
+        set_background(&mut window, config.background);
+        window.set_light(config.light.to_light());
         window.set_framerate_limit(Some(config.framerate as u64));
 
         let nodes = ObjectTracker::new(&mut window);
@@ -135,20 +387,64 @@ impl Parviewer {
         // capsule2.set_local_scale(diam, diam, diam);
         // capsule2.set_local_translation(na::Vec3::new(0.5, 0., 0.));
 
-        // TODO: config?
-        let dts_first = vec![
-            1., 2., 3., 4., 6., 8., 12., 16., 24., 32., 48., 64., 96., 128.,
-        ];
-        let mut dts = dts_first.iter().rev().map(|n| 1. / n).collect::<Vec<f32>>();
-        dts.extend(dts_first);
+        if let Some(ref analysis) = config.analysis {
+            analysis.add_values(&mut frames, config.sim_box.as_ref());
+        }
+
+        let speeds: Vec<f32> = config.speeds.iter().cloned().filter(|&n| n > 0.).collect();
+        let mut dts = speeds.iter().map(|n| 1. / n).collect::<Vec<f32>>();
+        dts.extend(speeds);
+        dts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
         dts.dedup();
 
         let mut timer = Timer::new(dts, Some(frames.len()));
 
         timer.loop_pause = config.pauseloop;
+        timer.start = config.start;
+        timer.end = config.end;
+        timer.stride = config.stride;
+        timer.pingpong = config.pingpong;
         timer.fps = config.framerate;
+        timer.timescale = config.timescale;
+        let times: Option<Vec<f32>> = frames.iter().map(|f| f.time).collect();
+        match times {
+            Some(times) => timer.set_frame_times(times),
+            None if config.timescale.is_some() => {
+                println!("Not all frames have a time; real-time playback is disabled.")
+            }
+            None => {}
+        }
+
+        let font = config.text.load_font()?;
+
+        let mut keymap = Keymap::default();
+        keymap.bind_names(&config.keys)?;
 
-        let font = kiss3d::text::Font::default();
+        let plot = match config.plot {
+            Some(ref plot_config) if !plot_config.values.is_empty() => {
+                Some(Plot::new(plot_config.clone(), &frames))
+            }
+            _ => None,
+        };
+
+        let triad = config.triad.clone().map(Triad::new);
+        let scale_bar = config.scale_bar.clone().map(ScaleBar::new);
+        let director = config.director.clone().map(Director::new);
+
+        let fov = config.fov;
+        let selection = config.follow.clone();
+        let following = selection.is_some();
+        let interpolate = config.interpolate;
+        let vector_field = config.vectors.show.clone();
+        let vectors = config.vectors.clone();
+        let show_contacts = config.contacts.show;
+        let contacts = config.contacts.clone();
+        let clusters = config.clusters.clone();
+        let color_mode = config.coloring.mode;
+        let coloring = config.coloring.clone();
+        let analysis = config.analysis.clone().unwrap_or_default();
+        let show_diff = config.diff.is_some();
+        let diff = config.diff.clone().unwrap_or_default();
 
         Ok(Parviewer {
             config,
@@ -157,8 +453,39 @@ impl Parviewer {
             timer,
             window,
             nodes,
+            others: vec![],
+            label: String::new(),
             camera,
+            fov,
+            follow_camera_path: true,
+            selection,
+            following,
+            interpolate,
+            vector_field,
+            vectors,
+            show_contacts,
+            contact_count: None,
+            contacts,
+            clusters,
+            cluster_colors: ClusterColors::default(),
+            cluster_stats: None,
+            color_mode,
+            coloring,
+            analysis,
+            show_diff,
+            diff,
+            reference: None,
+            frame_diff: None,
             font,
+            plot,
+            triad,
+            scale_bar,
+            director,
+            cursor: (0., 0.),
+            plot_drag: false,
+            keymap,
+            show_help: false,
+            snapshot_requested: false,
             paused: false,
         })
     }
@@ -174,164 +501,802 @@ impl Parviewer {
         &self.frames[ix]
     }
 
+    /// The frame shown in a viewport: the current frame of its trajectory, or the last one
+    /// if it is shorter.
+    fn viewport_frame(&self, viewport: usize) -> &Frame {
+        match viewport.checked_sub(1).and_then(|i| self.others.get(i)) {
+            None => self.get_frame(),
+            Some(other) => {
+                let ix = self.timer.get_index().min(other.frames.len() - 1);
+                &other.frames[ix]
+            }
+        }
+    }
+
+    /// Update the objects to the timer's position: the current frame, or when interpolating,
+    /// part of the way to the next one.
+    pub fn update_objects(&mut self) {
+        let ix = self.timer.get_index();
+        let next = self.timer.get_next().filter(|_| self.interpolate);
+        let frame = frame_at(&self.frames, ix, next);
+        let reference = match self.reference {
+            _ if !self.show_diff => None,
+            Some(ref frames) => frames.get(ix.min(frames.len() - 1)),
+            None => self
+                .frames
+                .get(self.diff.reference.min(self.frames.len() - 1)),
+        };
+        let vector_field = self.vector_field.as_deref();
+        let contacts = Some(&self.contacts).filter(|_| self.show_contacts);
+        let sim_box = self.config.sim_box.as_ref();
+        // Displacement coloring comes first, in the main viewport, then the coloring mode
+        let clusters = Some(&self.clusters).filter(|_| self.color_mode == ColorMode::Cluster);
+        let (frame, cluster_colors) = match clusters {
+            Some(config) if reference.is_none() => {
+                let (frame, colors, stats) =
+                    cluster_frame(frame, config, &mut self.cluster_colors, sim_box);
+                self.cluster_stats = Some(stats);
+                (frame, Some(colors))
+            }
+            _ => {
+                self.cluster_stats = None;
+                (frame, None)
+            }
+        };
+        let (extras, contact_count) =
+            glyphs(&frame, vector_field, &self.vectors, contacts, sim_box);
+        self.contact_count = contact_count;
+        match reference {
+            Some(reference) => {
+                let diff = FrameDiff::new(reference, &frame);
+                let shown = diff.with_disappeared(&frame);
+                self.nodes.update_with(&shown, extras, &mut self.palette);
+                for (name, color) in self.diff.colors(&diff) {
+                    self.nodes.set_color(&name, color);
+                }
+                self.frame_diff = Some(diff);
+            }
+            None => {
+                self.nodes.update_with(&frame, extras, &mut self.palette);
+                let colors = match cluster_colors {
+                    Some(colors) => Some(colors),
+                    None => self
+                        .coloring
+                        .colors(self.color_mode, &frame, &self.analysis, sim_box),
+                };
+                for (name, color) in colors.unwrap_or_default() {
+                    self.nodes.set_color(&name, color);
+                }
+                self.frame_diff = None;
+            }
+        }
+        for other in &mut self.others {
+            let frame = frame_at(&other.frames, ix, next);
+            // The other viewports are not compared with the reference, but follow the main
+            // one in leaving cluster coloring off while it is shown
+            let (frame, cluster_colors) = match clusters {
+                Some(config) if reference.is_none() => {
+                    let (frame, colors, _) =
+                        cluster_frame(frame, config, &mut other.cluster_colors, sim_box);
+                    (frame, Some(colors))
+                }
+                _ => (frame, None),
+            };
+            let (extras, _) = glyphs(&frame, vector_field, &self.vectors, contacts, sim_box);
+            other.nodes.update_with(&frame, extras, &mut other.palette);
+            let colors = match cluster_colors {
+                Some(colors) => Some(colors),
+                None => self
+                    .coloring
+                    .colors(self.color_mode, &frame, &self.analysis, sim_box),
+            };
+            for (name, color) in colors.unwrap_or_default() {
+                other.nodes.set_color(&name, color);
+            }
+        }
+    }
+
+    /// Compare each frame with the same frame of another trajectory of the same system,
+    /// instead of with a frame of this one, and turn on displacement coloring.
+    pub fn set_reference(&mut self, frames: Vec<Frame>) -> Result<(), Box<dyn Error>> {
+        if frames.is_empty() {
+            return Err("The reference trajectory has no frames".into());
+        }
+        self.reference = Some(frames);
+        self.show_diff = true;
+        Ok(())
+    }
+
+    /// Draw arrows from where each particle was in the reference to where it is, if
+    /// displacement coloring is on and `DiffConfig::arrows` is set.
+    pub fn draw_diff_arrows(&mut self) {
+        if !self.show_diff || !self.diff.arrows {
+            return;
+        }
+        let diff = match self.frame_diff {
+            Some(ref diff) => diff,
+            None => return,
+        };
+        let max = self.diff.scale_max(diff);
+        let view = self.camera.at() - self.camera.eye();
+        for &(_, from, displacement) in &diff.moved {
+            let distance = displacement.norm();
+            if distance < self.diff.min_arrow.max(EPSILON) {
+                continue;
+            }
+            let color = self.diff.color(distance, max).to_point3();
+            for (a, b) in diff::arrow_lines(from, from + displacement, &view) {
+                self.window.draw_line(&a, &b, &color);
+            }
+        }
+    }
+
+    /// Show another trajectory in its own viewport, beside the others, following the same
+    /// timer. Past the end of a shorter trajectory, its last frame is shown.
+    pub fn add_trajectory(
+        &mut self,
+        label: &str,
+        frames: Vec<Frame>,
+        palette: Palette,
+    ) -> Result<(), Box<dyn Error>> {
+        if frames.is_empty() {
+            return Err(format!("{} has no frames", label).into());
+        }
+        let nodes = ObjectTracker::new(&mut self.window);
+        self.others.push(Trajectory {
+            label: label.into(),
+            frames,
+            palette,
+            nodes,
+            cluster_colors: ClusterColors::default(),
+        });
+        self.layout_viewports();
+        Ok(())
+    }
+
+    /// Give each trajectory its part of the window, as set in `Config::viewports`
+    fn layout_viewports(&mut self) {
+        let config = &self.config.viewports;
+        let groups =
+            iter::once(self.nodes.group()).chain(self.others.iter().map(|t| t.nodes.group()));
+        let viewports = config
+            .layout(self.others.len() + 1)
+            .into_iter()
+            .zip(groups)
+            .enumerate()
+            .map(|(i, (rect, group))| Viewport {
+                rect,
+                group,
+                // The first viewport always uses the main camera
+                arcball: if i == 0 || config.linked {
+                    None
+                } else {
+                    Some(self.camera.arcball.clone())
+                },
+            })
+            .collect();
+        self.camera.viewports = viewports;
+    }
+
+    /// The current camera position
+    pub fn camera_state(&self) -> CameraState {
+        let at = self.camera.at();
+        CameraState {
+            yaw: self.camera.yaw() * 180. / PI,
+            pitch: self.camera.pitch() * 180. / PI,
+            distance: self.camera.dist(),
+            fov: self.fov,
+            target: (at.x, at.y, at.z),
+        }
+    }
+
+    /// Move the camera to a given position
+    pub fn set_camera_state(&mut self, state: &CameraState) {
+        let (x, y, z) = state.target;
+        let at = na::Point3::new(x, y, z);
+        if (state.fov - self.fov).abs() > EPSILON {
+            self.camera.set_fov(state.fov * PI / 180.);
+            self.fov = state.fov;
+        }
+        self.camera.set_at(at);
+        self.camera.set_yaw(state.yaw * PI / 180.);
+        self.camera.set_pitch(state.pitch * PI / 180.);
+        self.camera.set_dist(state.distance);
+    }
+
+    /// Move the camera along the scripted camera path, if there is one.
+    pub fn apply_camera_path(&mut self) {
+        if !self.follow_camera_path || self.config.camera_path.is_empty() {
+            return;
+        }
+        let mut state = self.camera_state();
+        self.config
+            .camera_path
+            .apply(self.timer.get_position(), &mut state);
+        self.set_camera_state(&state);
+    }
+
+    /// Stop following the scripted camera path, so the mouse can move the camera.
+    fn pause_camera_path(&mut self) {
+        if self.follow_camera_path && !self.config.camera_path.is_empty() {
+            self.follow_camera_path = false;
+            println!("Camera path paused; toggle_camera_path resumes it");
+        }
+    }
+
+    /// Move the camera target to a point, keeping the camera's angles and distance.
+    pub fn set_target(&mut self, target: na::Point3<f32>) {
+        self.camera.set_at(target);
+    }
+
+    /// The centre of the selection in the current frame, or of all particles if nothing is
+    /// selected.
+    pub fn selection_centre(&self) -> Option<na::Point3<f32>> {
+        let everything = ObjectID(vec![]);
+        let prefix = self.selection.as_ref().unwrap_or(&everything);
+        self.get_frame().centre(prefix)
+    }
+
+    /// Keep the selection at the view centre, if following it.
+    pub fn apply_follow(&mut self) {
+        if !self.following {
+            return;
+        }
+        if let Some(centre) = self.selection_centre() {
+            self.set_target(centre);
+        }
+    }
+
+    /// Move the camera target across the view, by a tenth of the camera distance.
+    pub fn pan(&mut self, direction: Direction) {
+        let at = self.camera.at();
+        let forward = (at - self.camera.eye()).normalize();
+        // The ArcBall keeps its pitch away from the poles, so this is never degenerate
+        let right = forward.cross(&na::Vector3::y()).normalize();
+        let up = right.cross(&forward);
+        let step = self.camera.dist() / 10.;
+        let shift = match direction {
+            Direction::Left => -right,
+            Direction::Right => right,
+            Direction::Up => up,
+            Direction::Down => -up,
+        };
+        self.set_target(at + shift * step);
+    }
+
+    /// The particle under the cursor, if any
+    pub fn pick(&self) -> Option<ObjectID> {
+        let (x, y) = misc::window_fraction(&self.window, self.cursor);
+        let (viewport, origin, dir) = self.camera.ray(x, y)?;
+        self.viewport_frame(viewport).pick(&origin, &dir).cloned()
+    }
+
     /// Draw some text in the window, with coordinates in the window frame (i.e., 0 to 1).
     pub fn draw_text(&mut self, t: &str, x: f32, y: f32, color: Color) {
-        let font_size = 48; // TODO draw_text takes a "scale", is using the font_size correct?
+        // TODO draw_text takes a "scale", is using the font_size correct?
+        let font_size = self.config.text.font_size;
+        self.draw_text_sized(t, x, y, font_size, color);
+    }
 
-        let max_width = self.window.width() * 2;
+    /// Draw some text in the window at a given font size, with coordinates in the window frame
+    /// (i.e., 0 to 1).
+    pub fn draw_text_sized(&mut self, t: &str, x: f32, y: f32, font_size: f32, color: Color) {
+        let max_width = self.window.width() as f32 * 2.;
         // TODO: Figure out why the bottom is window.height() * 2.
-        let max_height = self.window.height() * 2 - font_size; // TODO: Used to be - (self.font.height() as f32);
-        let text_loc = na::Point2::new(x * max_width as f32, y * max_height as f32);
+        let max_height = self.window.height() as f32 * 2. - font_size; // TODO: Used to be - (self.font.height() as f32);
+        let text_loc = na::Point2::new(x * max_width, y * max_height);
         let text_color = color.to_point3();
-        let font_size = 48; // TODO draw_text takes a "scale", is using the font_size correct?
         self.window
-            .draw_text(t, &text_loc, font_size as f32, &self.font, &text_color);
+            .draw_text(t, &text_loc, font_size, &self.font, &text_color);
+    }
+
+    /// Draw the plot of per-frame values, if one is configured.
+    pub fn draw_plot(&mut self) {
+        let ix = self.timer.get_index();
+        let (labels, font_size) = match self.plot {
+            None => return,
+            Some(ref plot) => {
+                plot.draw(&mut self.window, ix);
+                (plot.labels(), plot.font_size())
+            }
+        };
+        for (text, x, y, color) in labels {
+            self.draw_text_sized(&text, x, y, font_size, color);
+        }
+    }
+
+    /// Draw the axis triad, the scale bar and the director, if they are configured.
+    pub fn draw_overlays(&mut self) {
+        let mut labels = vec![];
+        if let Some(ref triad) = self.triad {
+            let font_size = triad.font_size();
+            for label in triad.draw(&mut self.window, &self.camera) {
+                labels.push((label, font_size));
+            }
+        }
+        if let Some(ref scale_bar) = self.scale_bar {
+            let target = self.camera.at();
+            if let Some(label) = scale_bar.draw(&mut self.window, &self.camera, &target) {
+                labels.push((label, scale_bar.font_size()));
+            }
+        }
+        if let Some(ref director) = self.director {
+            // Frames without spherocylinders have no director
+            if let Some(nematic) = analysis::nematic_order(self.get_frame()) {
+                let label = director.draw(&mut self.window, &self.camera, &nematic);
+                labels.push((label, director.font_size()));
+            }
+        }
+        for ((text, x, y, color), font_size) in labels {
+            self.draw_text_sized(&text, x, y, font_size, color);
+        }
+    }
+
+    /// Draw lines between the viewports, and label each with its trajectory, when comparing
+    /// trajectories.
+    pub fn draw_viewports(&mut self) {
+        if self.others.is_empty() {
+            return;
+        }
+        let color = self.config.text.color;
+        let line_color = color.to_point3();
+        let rects: Vec<Rect> = self.camera.viewports.iter().map(|v| v.rect).collect();
+        let labels: Vec<String> = iter::once(self.label.clone())
+            .chain(self.others.iter().map(|t| t.label.clone()))
+            .collect();
+        for (rect, label) in rects.into_iter().zip(labels) {
+            let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+            // Only the right and bottom edges, so each line is drawn once
+            let mut edges = vec![];
+            if right < 1. - EPSILON {
+                edges.push(((right, rect.y), (right, bottom)));
+            }
+            if bottom < 1. - EPSILON {
+                edges.push(((rect.x, bottom), (right, bottom)));
+            }
+            for ((ax, ay), (bx, by)) in edges {
+                let a = misc::planar_point(&self.window, ax, ay);
+                let b = misc::planar_point(&self.window, bx, by);
+                self.window.draw_planar_line(&a, &b, &line_color);
+            }
+            self.draw_text_sized(&label, rect.x + 0.01, bottom - 0.05, 24., color);
+        }
+    }
+
+    /// Seek to the frame under the cursor, if it is over the plot. Returns true if it was.
+    fn seek_plot(&mut self) -> bool {
+        let (x, y) = misc::window_fraction(&self.window, self.cursor);
+        match self.plot.as_ref().and_then(|p| p.index_at(x, y)) {
+            Some(ix) => {
+                self.timer.seek(ix);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Look up a piece of metadata for the current frame, for templates: the frame `index`,
+    /// or anything from `Frame::lookup`.
+    fn frame_lookup(&self, name: &str) -> Option<TemplateValue> {
+        let ix = self.timer.get_index();
+        match name {
+            "index" => Some(TemplateValue::Number(ix as f64)),
+            _ => self.frames[ix].lookup(name),
+        }
+    }
+
+    /// The text for the current frame: the frame's text, or the configured `text_format`
+    /// filled in with the frame's metadata.
+    pub fn frame_text(&self) -> String {
+        match self.config.text_format {
+            None => self.get_frame().text.clone(),
+            Some(ref template) => misc::format_template(template, |name| self.frame_lookup(name)),
+        }
     }
 
     /// Draw the text from the frame in the window, with coordinates in the window frame (i.e., 0 to 1).
     pub fn draw_frame_text(&mut self, x: f32, y: f32, color: Color) {
-        let font_size = 48; // TODO draw_text takes a "scale", is using the font_size correct?
+        let text = self.frame_text();
+        if !text.is_empty() {
+            self.draw_text(&*text, x, y, color);
+        }
+    }
 
-        let ix = self.timer.get_index();
-        let frame = &self.frames[ix];
-        if !frame.text.is_empty() {
-            let max_width = self.window.width() * 2;
-            // TODO: Figure out why the bottom is window.height() * 2.
-            let max_height = self.window.height() * 2 - font_size;
-            let text_loc = na::Point2::new(x * max_width as f32, y * max_height as f32);
-            let text_color = color.to_point3();
-
-            self.window.draw_text(
-                &*frame.text,
-                &text_loc,
-                font_size as f32,
-                &self.font,
-                &text_color,
+    /// The path to save a snapshot of the current frame to, from the configured directory and
+    /// file name template.
+    pub fn snapshot_path(&self) -> PathBuf {
+        self.config.snapshot.path(|name| self.frame_lookup(name))
+    }
+
+    /// Settings to export the current view as an SVG
+    pub fn svg_export(&self) -> SvgExport {
+        let (width, height) = misc::logical_size(&self.window);
+        SvgExport {
+            camera: self.camera_state(),
+            projection: self.camera.projection,
+            extent: self.camera.extent(),
+            width: width as u32,
+            height: height as u32,
+            background: if self.config.snapshot.transparent {
+                None
+            } else {
+                Some(self.config.background)
+            },
+            showbox: self.config.showbox,
+        }
+    }
+
+    /// Save the current frame as an SVG, as seen through the current camera.
+    pub fn export_svg(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let export = self.svg_export();
+        let index = self.timer.get_index();
+        export.save(&self.frames[index], &mut self.palette, path)
+    }
+
+    /// Save an image of the window to a file, as configured in `Config::snapshot`.
+    ///
+    /// Snapshots at the window size are copied from the window, with any text and overlays.
+    /// Larger or transparent snapshots are rendered again, and show only the 3D scene.
+    pub fn save_snapshot(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let config = self.config.snapshot.clone();
+        let image = if config.needs_render() {
+            self.render_image(config.scale.max(1), config.transparent)
+        } else {
+            DynamicImage::ImageRgb8(self.window.snap_image())
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        image.save(path)?;
+        Ok(())
+    }
+
+    /// Render the scene at `scale` times the window size, in tiles. If `transparent`, the
+    /// scene is rendered on black and on white, and the two are compared to find the alpha.
+    /// Each viewport is tiled separately, as the tiles are of each viewport's own view.
+    fn render_image(&mut self, scale: u32, transparent: bool) -> DynamicImage {
+        let rects = self.camera.pass_rects();
+        let backgrounds = if transparent {
+            vec![Color(0, 0, 0), Color(255, 255, 255)]
+        } else {
+            vec![self.config.background]
+        };
+
+        let mut layers: Vec<RgbImage> = vec![];
+        for &background in &backgrounds {
+            set_background(&mut self.window, background);
+            let mut layer: Option<RgbImage> = None;
+            for row in 0..scale {
+                for column in 0..scale {
+                    self.camera.tile = Some(Tile {
+                        count: scale,
+                        column,
+                        row,
+                    });
+                    let _ = self.window.render_with_camera(&mut self.camera);
+                    let mut tile = self.window.snap_image();
+                    let (width, height) = tile.dimensions();
+                    let layer =
+                        layer.get_or_insert_with(|| RgbImage::new(width * scale, height * scale));
+                    for rect in &rects {
+                        // This tile of the viewport's view, in its part of the window
+                        let (x, y, w, h) = rect.pixels(width, height);
+                        let part = imageops::crop(&mut tile, x, y, w, h);
+                        imageops::replace(
+                            layer,
+                            &part,
+                            x * scale + column * w,
+                            y * scale + row * h,
+                        );
+                    }
+                }
+            }
+            layers.extend(layer);
+        }
+        self.camera.tile = None;
+        set_background(&mut self.window, self.config.background);
+
+        match (transparent, layers.pop()) {
+            (true, Some(on_white)) => {
+                DynamicImage::ImageRgba8(snapshot::unblend(&layers[0], &on_white))
+            }
+            (_, Some(layer)) => DynamicImage::ImageRgb8(layer),
+            (_, None) => DynamicImage::new_rgb8(0, 0),
+        }
+    }
+
+    /// Save a snapshot of the current frame, reporting the result.
+    fn take_snapshot(&mut self) {
+        let path = self.snapshot_path();
+        match self.save_snapshot(&path) {
+            Ok(()) => println!("Saved image to {}", path.display()),
+            Err(err) => println!("Error saving {}: {}", path.display(), err),
+        }
+    }
+
+    /// Render a single frame and save it to `path`, without running the viewer. The frame is
+    /// shown whatever the playback range and stride.
+    pub fn render_snapshot(&mut self, index: usize, path: &Path) -> Result<(), Box<dyn Error>> {
+        if index >= self.frames.len() {
+            let msg = format!(
+                "Frame {} is out of range; there are {}",
+                index,
+                self.frames.len()
             );
+            return Err(msg.into());
+        }
+        self.timer.show_index(index);
+        self.update_objects();
+        self.apply_camera_path();
+        self.apply_follow();
+        // Render once, so the window and the objects are ready
+        let _ = self.window.render_with_camera(&mut self.camera);
+        self.save_snapshot(path)
+    }
+
+    /// Draw the list of key bindings.
+    pub fn draw_help(&mut self) {
+        let color = self.config.text.color;
+        let font_size = 24.;
+        let lines = self.keymap.help();
+        for (i, line) in lines.iter().enumerate() {
+            let y = 0.05 + 0.03 * i as f32;
+            self.draw_text_sized(line, 0.02, y, font_size, color);
+        }
+    }
+
+    /// Perform an action, such as one bound to a key.
+    pub fn perform(&mut self, action: &Action) {
+        match *action {
+            Action::Ignore => {}
+            Action::Quit => {
+                self.window.close();
+            }
+            Action::Pause => {
+                self.paused = !self.paused;
+            }
+            Action::Faster => {
+                self.timer.faster();
+            }
+            Action::Slower => {
+                self.timer.slower();
+            }
+            Action::Reverse => {
+                self.timer.switch_direction();
+            }
+            Action::View(view) => {
+                let (yaw, pitch) = view.angles();
+                self.camera.set_yaw(yaw * PI / 180.);
+                self.camera.set_pitch(pitch * PI / 180.);
+            }
+            Action::ToggleProjection => {
+                self.camera.toggle_projection();
+            }
+            Action::Snapshot => {
+                // Taken after the next render, so the image matches the window
+                self.snapshot_requested = true;
+            }
+            Action::ExportSvg => {
+                let path = self.snapshot_path().with_extension("svg");
+                match self.export_svg(&path) {
+                    Ok(()) => println!("Saved SVG to {}", path.display()),
+                    Err(err) => println!("Error saving {}: {}", path.display(), err),
+                }
+            }
+            Action::PrintCamera => {
+                let at = self.camera.at();
+                println!(
+                    "yaw: {:6.2}, pitch: {:6.2}, distance: {:6.2}, target: [{:.3}, {:.3}, {:.3}]",
+                    self.camera.yaw() * 180. / PI,
+                    self.camera.pitch() * 180. / PI,
+                    self.camera.dist(),
+                    at.x,
+                    at.y,
+                    at.z
+                );
+            }
+            Action::TogglePartial(n) => {
+                self.palette.toggle_partial(n);
+            }
+            Action::AllPartials => {
+                self.palette.set_all_partial(true);
+            }
+            Action::NoPartials => {
+                self.palette.set_all_partial(false);
+            }
+            Action::Pan(direction) => {
+                // Panning by hand would be undone by following
+                self.following = false;
+                self.pan(direction);
+            }
+            Action::CenterSelection => {
+                if let Some(centre) = self.selection_centre() {
+                    self.set_target(centre);
+                }
+            }
+            Action::ToggleFollow => {
+                self.following = !self.following;
+            }
+            Action::ResetTarget => {
+                self.following = false;
+                let (x, y, z) = self.config.target;
+                self.set_target(na::Point3::new(x, y, z));
+            }
+            Action::ToggleCameraPath => {
+                self.follow_camera_path = !self.follow_camera_path;
+            }
+            Action::NextVector => {
+                let names = self.get_frame().vector_names();
+                self.vector_field = vectors::next_vector(&names, self.vector_field.as_deref());
+                match self.vector_field {
+                    Some(ref name) => println!("Showing {}", name),
+                    None => println!("Showing no vectors"),
+                }
+                self.update_objects();
+            }
+            Action::ToggleContacts => {
+                self.show_contacts = !self.show_contacts;
+                self.update_objects();
+                if let Some(count) = self.contact_count {
+                    println!("{} contacts", count);
+                }
+            }
+            Action::NextColoring => {
+                self.color_mode = self.color_mode.next();
+                println!("Coloring by {}", self.color_mode.describe());
+                self.update_objects();
+                if let Some(stats) = self.cluster_stats {
+                    println!("{}", stats.describe());
+                }
+            }
+            Action::ToggleDiff => {
+                self.show_diff = !self.show_diff;
+                self.update_objects();
+            }
+            Action::Help => {
+                self.show_help = !self.show_help;
+            }
+            Action::Custom(ref name) => {
+                println!("No hook handled the action '{}'", name);
+            }
         }
     }
 
     /// Standard key handling, called by run.
     pub fn handle_events(&mut self) {
+        self.handle_events_with_hooks(&mut [])
+    }
+
+    /// Key and mouse handling, offering each event to the hooks first.
+    pub fn handle_events_with_hooks(&mut self, hooks: &mut [&mut dyn Hook]) {
         for mut event in self.window.events().iter() {
-            match event.value {
-                WindowEvent::Key(key, Action::Release, _) => {
-                    // Default to inhibiting, although this can be overridden
-                    let mut inhibit = true;
-                    match key {
-                        Key::Q => {
-                            self.window.close();
-                            return;
-                        }
-                        Key::Comma => {
-                            self.timer.slower();
-                        }
-                        Key::Period => {
-                            self.timer.faster();
-                        }
-                        Key::F => {
-                            self.timer.switch_direction();
-                        }
-                        Key::Up => {
-                            self.camera.set_pitch(PI / 3.);
-                            self.camera.set_yaw(PI / 4.);
-                        }
-                        Key::Down => {
-                            self.camera.set_pitch(PI / 2.);
-                            self.camera.set_yaw(0.);
-                        }
-                        Key::S => {
-                            // TODO: savefile format should be a config option
-                            let fname = format!("frame{:04}.png", self.timer.get_index());
-                            let path = Path::new(&fname);
-                            let img = self.window.snap_image();
-                            match img.save(path) {
-                                Ok(()) => println!("Saved image to {}", fname),
-                                Err(res) => println!("Error saving: {}", res),
-                            };
-                        }
-                        Key::W => {
-                            println!(
-                                "yaw: {:6.2}, pitch: {:6.2}, distance: {:6.2}",
-                                self.camera.yaw() * 180. / PI,
-                                self.camera.pitch() * 180. / PI,
-                                self.camera.dist()
-                            );
-                        }
-                        Key::Space => {
-                            self.paused = !self.paused;
-                        }
-                        Key::Key1 => {
-                            self.palette.toggle_partial(0);
-                        }
-                        Key::Key2 => {
-                            self.palette.toggle_partial(1);
-                        }
-                        Key::Key3 => {
-                            self.palette.toggle_partial(2);
-                        }
-                        Key::Key4 => {
-                            self.palette.toggle_partial(3);
-                        }
-                        Key::Key5 => {
-                            self.palette.toggle_partial(4);
-                        }
-                        Key::Key6 => {
-                            self.palette.toggle_partial(5);
-                        }
-                        Key::Key7 => {
-                            self.palette.toggle_partial(6);
-                        }
-                        Key::Key8 => {
-                            self.palette.toggle_partial(7);
-                        }
-                        Key::Key9 => {
-                            self.palette.set_all_partial(true);
-                        }
-                        Key::Key0 => {
-                            self.palette.set_all_partial(false);
+            let value = event.value;
+            let consumed = match value {
+                WindowEvent::Key(key, action, modifiers) => hooks
+                    .iter_mut()
+                    .any(|h| h.on_key(self, key, action, modifiers) == EventResult::Consume),
+                WindowEvent::CursorPos(..)
+                | WindowEvent::MouseButton(..)
+                | WindowEvent::Scroll(..) => hooks
+                    .iter_mut()
+                    .any(|h| h.on_mouse(self, &value) == EventResult::Consume),
+                _ => false,
+            };
+            if consumed {
+                event.inhibited = true;
+                continue;
+            }
+
+            match value {
+                WindowEvent::Key(key, event::Action::Release, modifiers) => {
+                    let action = self.keymap.get(&Chord::new(key, modifiers)).cloned();
+                    if let Some(action) = action {
+                        event.inhibited = true;
+                        let handled = hooks
+                            .iter_mut()
+                            .any(|h| h.on_action(self, &action) == EventResult::Consume);
+                        if !handled {
+                            self.perform(&action);
                         }
-                        code => {
-                            println!("You released the key with code: {:?}", code);
-                            inhibit = false;
+                        if action == Action::Quit {
+                            return;
                         }
                     }
-                    event.inhibited = inhibit;
+                }
+                WindowEvent::CursorPos(x, y, _) => {
+                    self.cursor = (x, y);
+                    if self.plot_drag {
+                        // Scrub along the plot, rather than rotating the view
+                        let _ = self.seek_plot();
+                        event.inhibited = true;
+                    }
+                }
+                WindowEvent::MouseButton(MouseButton::Button1, event::Action::Press, _) => {
+                    if self.seek_plot() {
+                        self.plot_drag = true;
+                        event.inhibited = true;
+                    } else {
+                        self.pause_camera_path();
+                    }
+                }
+                WindowEvent::MouseButton(MouseButton::Button2, event::Action::Press, _)
+                | WindowEvent::Scroll(..) => {
+                    // The camera path would undo moving the camera by hand
+                    self.pause_camera_path();
+                }
+                WindowEvent::MouseButton(MouseButton::Button1, event::Action::Release, _) => {
+                    self.plot_drag = false;
+                }
+                WindowEvent::MouseButton(MouseButton::Button3, event::Action::Release, _) => {
+                    // Middle click selects a particle, or clears the selection
+                    self.selection = self.pick();
+                    match self.selection {
+                        Some(ref names) => println!("Selected {:?}", names.0),
+                        None => println!("Selection cleared"),
+                    }
                 }
                 _ => {}
             }
         }
     }
 
-    /// Start the whole running sequence.
-    pub fn run<F>(&mut self, mut update: F)
+    /// Start the whole running sequence, calling `update(viewer, new_index)` after each frame.
+    pub fn run<F>(&mut self, update: F)
     where
         F: FnMut(&mut Parviewer, bool),
     {
-        {
-            // Set it to the first position, and then return the borrow of `self` for
-            // the render function to use
-            let ref frame = self.frames[0];
-            self.nodes.update(frame, &mut self.palette);
-        }
+        let mut hook = UpdateHook::new(update);
+        let hook_ref: &mut dyn Hook = &mut hook;
+        self.run_with_hooks(&mut [hook_ref]);
+    }
 
-        let mut lastframe: isize = 0;
+    /// Start the whole running sequence, calling each hook at the appropriate points.
+    pub fn run_with_hooks(&mut self, hooks: &mut [&mut dyn Hook]) {
+        // Set it to the first position
+        self.update_objects();
+
+        let mut lastframe: isize = self.timer.get_index() as isize;
         while self.window.render_with_camera(&mut self.camera) {
+            if self.snapshot_requested {
+                self.snapshot_requested = false;
+                self.take_snapshot();
+            }
+
+            for hook in hooks.iter_mut() {
+                hook.pre_frame(self);
+            }
+
             if !self.paused {
                 self.timer.incr();
             }
             let ix = self.timer.get_index();
 
             let new_index = lastframe != (ix as isize);
+            if new_index || self.interpolate {
+                self.update_objects();
+            }
             if new_index {
-                let ref frame = self.frames[ix];
-                self.nodes.update(frame, &mut self.palette);
                 lastframe = ix as isize;
+                for hook in hooks.iter_mut() {
+                    hook.on_index_change(self, ix);
+                }
             }
 
-            update(self, new_index);
+            self.apply_camera_path();
+            self.apply_follow();
+            self.draw_plot();
+            self.draw_diff_arrows();
+            self.draw_overlays();
+            self.draw_viewports();
+            if self.show_help {
+                self.draw_help();
+            }
+
+            for hook in hooks.iter_mut() {
+                hook.post_frame(self);
+            }
 
-            self.handle_events();
+            self.handle_events_with_hooks(hooks);
         }
     }
 }
@@ -0,0 +1,204 @@
+//! A small line plot of per-frame scalar values, drawn in a corner of the window.
+
+use na;
+use std;
+
+use kiss3d::window::Window;
+use serde::{Deserialize, Serialize};
+
+use misc;
+use misc::TemplateValue;
+use objects::Frame;
+use palette::{Color, DEFAULT_COLORS};
+
+/// Configuration for the plot panel
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct PlotConfig {
+    /// Names of the values to plot: `time`, `step`, or keys of a frame's `values`
+    pub values: Vec<String>,
+    /// Top left corner of the plot, in the window frame (0 to 1)
+    pub position: (f32, f32),
+    /// Width and height of the plot, in the window frame (0 to 1)
+    pub size: (f32, f32),
+    /// Color of each line. Defaults to the palette's default colors.
+    pub colors: Vec<Color>,
+    /// Color of the frame and the cursor
+    pub axis_color: Color,
+    /// Font size of the labels
+    pub font_size: f32,
+}
+
+impl Default for PlotConfig {
+    fn default() -> Self {
+        PlotConfig {
+            values: vec![],
+            position: (0.6, 0.05),
+            size: (0.35, 0.2),
+            colors: DEFAULT_COLORS
+                .iter()
+                .map(|&(r, g, b)| Color(r, g, b))
+                .collect(),
+            axis_color: Color(200, 200, 200),
+            font_size: 24.,
+        }
+    }
+}
+
+/// A single line of the plot, normalized to run from 0 to 1
+struct Series {
+    name: String,
+    color: Color,
+    points: Vec<Option<f32>>,
+}
+
+impl Series {
+    fn new(name: &str, color: Color, frames: &[Frame]) -> Series {
+        let values: Vec<Option<f64>> = frames
+            .iter()
+            .map(|f| match f.lookup(name) {
+                Some(TemplateValue::Number(v)) if v.is_finite() => Some(v),
+                _ => None,
+            })
+            .collect();
+
+        let (lo, hi) = values.iter().filter_map(|&v| v).fold(
+            (std::f64::INFINITY, std::f64::NEG_INFINITY),
+            |(lo, hi), v| (lo.min(v), hi.max(v)),
+        );
+
+        let points = values
+            .iter()
+            .map(|v| {
+                v.map(|v| {
+                    if hi > lo {
+                        ((v - lo) / (hi - lo)) as f32
+                    } else {
+                        0.5
+                    }
+                })
+            })
+            .collect();
+
+        Series {
+            name: name.to_string(),
+            color,
+            points,
+        }
+    }
+}
+
+/// A plot of per-frame values across the whole trajectory, with a cursor at the current frame.
+pub struct Plot {
+    config: PlotConfig,
+    series: Vec<Series>,
+    len: usize,
+}
+
+impl Plot {
+    /// Create a plot of the configured values. Each line is scaled to fill the plot.
+    pub fn new(config: PlotConfig, frames: &[Frame]) -> Plot {
+        let default_colors = PlotConfig::default().colors;
+        let colors = if config.colors.is_empty() {
+            &default_colors
+        } else {
+            &config.colors
+        };
+        let series = config
+            .values
+            .iter()
+            .zip(colors.iter().cycle())
+            .map(|(name, &color)| Series::new(name, color, frames))
+            .collect();
+
+        Plot {
+            series,
+            len: frames.len(),
+            config,
+        }
+    }
+
+    /// Convert a position in the plot (0 to 1, y up) to the window frame
+    fn to_window(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x0, y0) = self.config.position;
+        let (w, h) = self.config.size;
+        (x0 + x * w, y0 + (1. - y) * h)
+    }
+
+    /// x position in the plot (0 to 1) for a given frame index
+    fn index_x(&self, index: usize) -> f32 {
+        if self.len > 1 {
+            index as f32 / (self.len - 1) as f32
+        } else {
+            0.5
+        }
+    }
+
+    /// If the point (in the window frame) is within the plot, return the nearest frame index.
+    pub fn index_at(&self, x: f32, y: f32) -> Option<usize> {
+        let (x0, y0) = self.config.position;
+        let (w, h) = self.config.size;
+        if self.len == 0 || x < x0 || x > x0 + w || y < y0 || y > y0 + h {
+            return None;
+        }
+        let fx = (x - x0) / w;
+        Some((fx * (self.len - 1) as f32).round() as usize)
+    }
+
+    /// Draw the frame, the lines, and a cursor at the given index.
+    pub fn draw(&self, window: &mut Window, index: usize) {
+        let axis_color = self.config.axis_color.to_point3();
+        let corners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.), (0., 0.)];
+        for pair in corners.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            self.draw_line(window, a, b, &axis_color);
+        }
+
+        for series in &self.series {
+            let color = series.color.to_point3();
+            for (i, pair) in series.points.windows(2).enumerate() {
+                if let (Some(a), Some(b)) = (pair[0], pair[1]) {
+                    let (xa, xb) = (self.index_x(i), self.index_x(i + 1));
+                    self.draw_line(window, (xa, a), (xb, b), &color);
+                }
+            }
+        }
+
+        let x = self.index_x(index);
+        self.draw_line(window, (x, 0.), (x, 1.), &axis_color);
+    }
+
+    fn draw_line(
+        &self,
+        window: &mut Window,
+        a: (f32, f32),
+        b: (f32, f32),
+        color: &na::Point3<f32>,
+    ) {
+        let (ax, ay) = self.to_window(a.0, a.1);
+        let (bx, by) = self.to_window(b.0, b.1);
+        let pa = misc::planar_point(window, ax, ay);
+        let pb = misc::planar_point(window, bx, by);
+        window.draw_planar_line(&pa, &pb, color);
+    }
+
+    /// Labels for each line, as (text, x, y, color), with positions in the window frame.
+    pub fn labels(&self) -> Vec<(String, f32, f32, Color)> {
+        let (x0, y0) = self.config.position;
+        let (_, h) = self.config.size;
+        let line_height = 0.03;
+        self.series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let y = y0 + h + line_height * (i + 1) as f32;
+                (s.name.clone(), x0, y, s.color)
+            })
+            .collect()
+    }
+
+    /// Font size for the labels
+    pub fn font_size(&self) -> f32 {
+        self.config.font_size
+    }
+}
@@ -0,0 +1,301 @@
+//! POV-Ray export: write a frame, its colors, and the camera as a `.pov` scene for ray-tracing.
+//!
+//! The scene keeps the viewer's right-handed coordinates; the camera's `right` vector points
+//! along -x, the usual way of making POV-Ray (which is left-handed) match. Render with e.g.
+//! `povray +W1600 +H1200 +A scene.pov`, adding `+UA` for a transparent background.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use camera::{CameraState, Projection, ZFAR};
+use misc;
+use objects::Frame;
+use palette::{Color, Palette};
+use parviewer::Config;
+use style::LightMode;
+
+/// A camera, lighting and the other settings needed to write a frame as a POV-Ray scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PovExport {
+    /// Camera position
+    pub camera: CameraState,
+    /// Camera projection
+    pub projection: Projection,
+    /// Width of the orthographic view
+    pub extent: f32,
+    /// Image width, in pixels; only the aspect ratio is used
+    pub width: u32,
+    /// Image height, in pixels; only the aspect ratio is used
+    pub height: u32,
+    /// Background color. None leaves the background transparent.
+    pub background: Option<Color>,
+    /// Where the scene is lit from
+    pub light: LightMode,
+    /// Draw the edges of the unit box
+    pub showbox: bool,
+}
+
+/// A POV-Ray vector
+fn vector((x, y, z): (f32, f32, f32)) -> String {
+    format!("<{}, {}, {}>", x, y, z)
+}
+
+/// A POV-Ray color, in the same sRGB values as the palette
+fn srgb(color: Color) -> String {
+    format!("srgb {}", vector(color.to_floats()))
+}
+
+/// The name of the texture declared for a color
+fn texture_name(color: Color) -> String {
+    let Color(r, g, b) = color;
+    format!("Particle_{:02x}{:02x}{:02x}", r, g, b)
+}
+
+impl PovExport {
+    /// Settings matching the initial camera of a viewer with this config.
+    pub fn new(config: &Config) -> PovExport {
+        PovExport {
+            camera: config.initial_camera(),
+            projection: config.projection,
+            extent: config.ortho_extent(),
+            width: config.width,
+            height: config.height,
+            background: if config.snapshot.transparent {
+                None
+            } else {
+                Some(config.background)
+            },
+            light: config.light,
+            showbox: config.showbox,
+        }
+    }
+
+    fn write_camera(&self, pov: &mut String) {
+        let aspect = self.width as f32 / self.height.max(1) as f32;
+        let at = self.camera.at();
+        let eye = self.camera.eye();
+        let _ = writeln!(pov, "camera {{");
+        match self.projection {
+            Projection::Perspective => {
+                // POV-Ray's angle is horizontal; the viewer's field of view is vertical
+                let fov = self.camera.fov * PI / 180.;
+                let angle = 2. * ((fov / 2.).tan() * aspect).atan() * 180. / PI;
+                let _ = writeln!(pov, "    perspective");
+                let _ = writeln!(pov, "    location {}", vector((eye.x, eye.y, eye.z)));
+                let _ = writeln!(pov, "    right {}", vector((-aspect, 0., 0.)));
+                let _ = writeln!(pov, "    up {}", vector((0., 1., 0.)));
+                let _ = writeln!(pov, "    angle {}", angle);
+            }
+            Projection::Orthographic => {
+                // Orthographic cameras only see what is in front of them, so back away
+                let location = at + (eye - at).normalize() * (ZFAR / 2.);
+                let (w, h) = (self.extent, self.extent / aspect);
+                let _ = writeln!(pov, "    orthographic");
+                let _ = writeln!(
+                    pov,
+                    "    location {}",
+                    vector((location.x, location.y, location.z))
+                );
+                let _ = writeln!(pov, "    right {}", vector((-w, 0., 0.)));
+                let _ = writeln!(pov, "    up {}", vector((0., h, 0.)));
+            }
+        }
+        let _ = writeln!(pov, "    look_at {}", vector((at.x, at.y, at.z)));
+        let _ = writeln!(pov, "}}");
+    }
+
+    /// Write a frame as a POV-Ray scene. Colors come from the palette, as in the viewer.
+    pub fn render(&self, frame: &Frame, palette: &mut Palette) -> String {
+        let mut pov = String::new();
+        let _ = writeln!(pov, "// Written by parview");
+        let _ = writeln!(pov, "#version 3.7;");
+        let _ = writeln!(pov, "global_settings {{ assumed_gamma 1.0 }}");
+        let _ = writeln!(pov);
+
+        match self.background {
+            Some(color) => {
+                let _ = writeln!(pov, "background {{ color {} }}", srgb(color));
+            }
+            None => {
+                let _ = writeln!(pov, "background {{ color rgbt <0, 0, 0, 1> }}");
+            }
+        }
+        self.write_camera(&mut pov);
+        let light = match self.light {
+            LightMode::StickToCamera => {
+                let eye = self.camera.eye();
+                (eye.x, eye.y, eye.z)
+            }
+            LightMode::Absolute(p) => p,
+        };
+        let _ = writeln!(pov, "light_source {{ {} color rgb 1 }}", vector(light));
+        let _ = writeln!(pov);
+
+        // One texture per color, declared before use
+        let sphere_colors: Vec<Color> = frame
+            .spheres
+            .iter()
+            .map(|s| palette.get_color(&s.names))
+            .collect();
+        let spherocylinder_colors: Vec<Color> = frame
+            .spherocylinders
+            .iter()
+            .map(|s| palette.get_color(&s.names))
+            .collect();
+        let colors: BTreeSet<Color> = sphere_colors
+            .iter()
+            .chain(spherocylinder_colors.iter())
+            .cloned()
+            .collect();
+        let _ = writeln!(
+            pov,
+            "#declare ParticleFinish = finish {{ ambient 0.15 diffuse 0.75 phong 0.4 phong_size 40 }}"
+        );
+        for &color in &colors {
+            let _ = writeln!(
+                pov,
+                "#declare {} = texture {{ pigment {{ color {} }} finish {{ ParticleFinish }} }}",
+                texture_name(color),
+                srgb(color)
+            );
+        }
+        let _ = writeln!(pov);
+
+        for (sphere, &color) in frame.spheres.iter().zip(&sphere_colors) {
+            let _ = writeln!(
+                pov,
+                "sphere {{ {}, {} texture {{ {} }} }}",
+                vector(sphere.loc),
+                sphere.diameter / 2.,
+                texture_name(color)
+            );
+        }
+
+        for (spherocylinder, &color) in frame.spherocylinders.iter().zip(&spherocylinder_colors) {
+            let center = spherocylinder.x();
+            let axis = spherocylinder.get_axis();
+            let length = axis.norm();
+            let radius = spherocylinder.diameter / 2.;
+            if length <= spherocylinder.diameter {
+                let _ = writeln!(
+                    pov,
+                    "sphere {{ {}, {} texture {{ {} }} }}",
+                    vector(spherocylinder.loc),
+                    radius,
+                    texture_name(color)
+                );
+                continue;
+            }
+            // The axis runs from cap to cap; the caps are centred a radius in from each end
+            let half = axis * ((length - spherocylinder.diameter) / (2. * length));
+            let (a, b) = (center - half, center + half);
+            let _ = writeln!(
+                pov,
+                "sphere_sweep {{ linear_spline 2, {}, {}, {}, {} texture {{ {} }} }}",
+                vector((a.x, a.y, a.z)),
+                radius,
+                vector((b.x, b.y, b.z)),
+                radius,
+                texture_name(color)
+            );
+        }
+
+        if self.showbox {
+            let _ = writeln!(pov, "union {{");
+            for (a, b) in misc::box_edges() {
+                let _ = writeln!(
+                    pov,
+                    "    sphere_sweep {{ linear_spline 2, {}, {}, {}, {} }}",
+                    vector((a.x, a.y, a.z)),
+                    misc::BOX_EDGE_RADIUS,
+                    vector((b.x, b.y, b.z)),
+                    misc::BOX_EDGE_RADIUS
+                );
+            }
+            let _ = writeln!(
+                pov,
+                "    texture {{ pigment {{ color rgb <1, 0, 0> }} finish {{ ParticleFinish }} }}"
+            );
+            let _ = writeln!(pov, "}}");
+        }
+        pov
+    }
+
+    /// Write a frame, and save it to a file.
+    pub fn save(
+        &self,
+        frame: &Frame,
+        palette: &mut Palette,
+        path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.render(frame, palette))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+use objects::{ObjectID, Sphere, Spherocylinder};
+
+#[test]
+fn pov_scene() {
+    let frame = Frame {
+        spheres: vec![Sphere {
+            loc: (0.25, 0., 0.),
+            diameter: 0.2,
+            names: ObjectID(vec!["A".into()]),
+            vectors: Default::default(),
+        }],
+        spherocylinders: vec![Spherocylinder {
+            loc: (0., 0., 0.),
+            axis: (0., 0.5, 0.),
+            diameter: 0.1,
+            names: ObjectID(vec!["B".into()]),
+            vectors: Default::default(),
+        }],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: Default::default(),
+        forces: vec![],
+    };
+    let mut palette = Palette::default();
+    let export = PovExport {
+        camera: CameraState {
+            yaw: 90.,
+            pitch: 90.,
+            distance: 2.,
+            fov: 45.,
+            target: (0., 0., 0.),
+        },
+        projection: Projection::Perspective,
+        extent: 1.,
+        width: 800,
+        height: 600,
+        background: Some(Color(255, 255, 255)),
+        light: LightMode::StickToCamera,
+        showbox: true,
+    };
+    let pov = export.render(&frame, &mut palette);
+    let a = texture_name(palette.get_color(&ObjectID(vec!["A".into()])));
+
+    assert!(pov.contains("background { color srgb <1, 1, 1> }"));
+    // Looking from +z, with the vertical field of view of 45° made horizontal
+    assert!(pov.contains("angle 57.8224"));
+    assert!(pov.contains("look_at <0, 0, 0>"));
+    assert!(pov.contains("right <-1.3333334, 0, 0>"));
+    assert!(pov.contains(&format!("#declare {} = texture", a)));
+    assert!(pov.contains(&format!(
+        "sphere {{ <0.25, 0, 0>, 0.1 texture {{ {} }} }}",
+        a
+    )));
+    // The caps of a spherocylinder of length 0.5 are 0.4 apart
+    assert!(pov.contains("sphere_sweep { linear_spline 2, <0, -0.2, 0>, 0.05, <0, 0.2, 0>, 0.05"));
+    assert_eq!(pov.matches("sphere_sweep").count(), 13);
+}
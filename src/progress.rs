@@ -0,0 +1,130 @@
+//! A progress bar for the terminal, for long recordings and exports.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Width of the bar, in characters
+const BAR_WIDTH: usize = 30;
+
+/// Shortest time between redrawing the progress bar
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Draws a progress bar with the rate and estimated time remaining on one line of standard
+/// error, overwriting it as it goes.
+pub struct Progress {
+    /// Items done before this run, as when resuming; they do not count towards the rate
+    first: usize,
+    /// Total items, if known
+    total: Option<usize>,
+    start: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    /// Start counting from `first` items done, out of `total`.
+    pub fn new(first: usize, total: Option<usize>) -> Progress {
+        Progress {
+            first,
+            total,
+            start: Instant::now(),
+            last_draw: None,
+        }
+    }
+
+    /// Show that `done` items are done. The bar is redrawn at most ten times a second, and
+    /// when everything is done.
+    pub fn set(&mut self, done: usize) {
+        let now = Instant::now();
+        let finished = self.total.map_or(false, |total| done >= total);
+        match self.last_draw {
+            Some(last) if !finished && now.duration_since(last) < REDRAW_INTERVAL => return,
+            _ => {}
+        }
+        self.last_draw = Some(now);
+        let line = progress_line(
+            done.saturating_sub(self.first),
+            done,
+            self.total,
+            now.duration_since(self.start),
+        );
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\r{}", line);
+        let _ = stderr.flush();
+    }
+
+    /// End the line, so later output starts on a new one.
+    pub fn finish(&mut self) {
+        if self.last_draw.is_some() {
+            eprintln!();
+        }
+        self.last_draw = None;
+    }
+}
+
+/// Format a duration as `h:mm:ss`
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+/// A line showing `done` out of `total` items, the rate of the `new` items done in `elapsed`,
+/// and the time left at that rate.
+pub fn progress_line(new: usize, done: usize, total: Option<usize>, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    let rate = if seconds > 0. {
+        new as f64 / seconds
+    } else {
+        0.
+    };
+    match total {
+        Some(total) => {
+            let fraction = if total > 0 {
+                (done as f64 / total as f64).min(1.)
+            } else {
+                1.
+            };
+            let filled = (fraction * BAR_WIDTH as f64) as usize;
+            let eta = if rate > 0. {
+                format_duration(total.saturating_sub(done) as f64 / rate)
+            } else {
+                "?".into()
+            };
+            format!(
+                "[{}{}] {}/{} {:3.0}% {:.1} fps ETA {}",
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                done,
+                total,
+                fraction * 100.,
+                rate,
+                eta
+            )
+        }
+        None => format!("{} frames {:.1} fps", done, rate),
+    }
+}
+
+#[test]
+fn progress_lines() {
+    let line = progress_line(40, 50, Some(100), Duration::from_secs(20));
+    assert_eq!(
+        line,
+        format!(
+            "[{}{}] 50/100  50% 2.0 fps ETA 0:00:25",
+            "#".repeat(15),
+            "-".repeat(15)
+        )
+    );
+    let line = progress_line(0, 0, Some(7200), Duration::from_secs(0));
+    assert!(line.ends_with("0/7200   0% 0.0 fps ETA ?"));
+    assert_eq!(format_duration(3725.), "1:02:05");
+    assert_eq!(
+        progress_line(12, 12, None, Duration::from_secs(4)),
+        "12 frames 3.0 fps"
+    );
+}
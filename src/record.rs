@@ -0,0 +1,89 @@
+//! Frame-exact recording for `pvrecord`: a fixed schedule of video frames, independent of the
+//! playback clock.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+use toml;
+
+/// How `pvrecord` steps through the trajectory
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct RecordConfig {
+    /// Record a fixed schedule of video frames, set by the other fields, instead of following
+    /// the playback clock. Playback speed, `pauseloop` and `pingpong` do not apply.
+    pub exact: bool,
+    /// Video frames for each frame of the trajectory
+    pub hold: usize,
+    /// Video frames between each frame of the trajectory and the next, with the particles
+    /// moved part of the way between them
+    pub inbetween: usize,
+    /// Extra video frames of the last frame of the trajectory
+    pub end_hold: usize,
+}
+
+impl Default for RecordConfig {
+    fn default() -> Self {
+        RecordConfig {
+            exact: false,
+            hold: 1,
+            inbetween: 0,
+            end_hold: 0,
+        }
+    }
+}
+
+impl RecordConfig {
+    /// Number of video frames recorded for `steps` frames of the trajectory
+    pub fn frame_count(&self, steps: usize) -> usize {
+        if steps == 0 {
+            return 0;
+        }
+        steps * self.hold.max(1) + (steps - 1) * self.inbetween + self.end_hold
+    }
+
+    /// The position of each video frame, in steps through the playback range (as in
+    /// `Timer::t`), for `steps` frames of the trajectory.
+    pub fn schedule(&self, steps: usize) -> Vec<f32> {
+        let mut positions = Vec::with_capacity(self.frame_count(steps));
+        for step in 0..steps {
+            for _ in 0..self.hold.max(1) {
+                positions.push(step as f32);
+            }
+            if step + 1 == steps {
+                break;
+            }
+            for k in 1..=self.inbetween {
+                let fraction = k as f32 / (self.inbetween + 1) as f32;
+                positions.push(step as f32 + fraction);
+            }
+        }
+        if steps > 0 {
+            for _ in 0..self.end_hold {
+                positions.push((steps - 1) as f32);
+            }
+        }
+        positions
+    }
+}
+
+#[test]
+fn record_schedule() {
+    let config: RecordConfig = toml::from_str("exact = true\ninbetween = 3\nend_hold = 2").unwrap();
+    assert_eq!(config.hold, 1);
+    let positions = config.schedule(3);
+    assert_eq!(
+        positions,
+        vec![0., 0.25, 0.5, 0.75, 1., 1.25, 1.5, 1.75, 2., 2., 2.]
+    );
+    assert_eq!(positions.len(), config.frame_count(3));
+
+    let config = RecordConfig {
+        hold: 2,
+        ..Default::default()
+    };
+    assert_eq!(config.schedule(3), vec![0., 0., 1., 1., 2., 2.]);
+    assert_eq!(config.frame_count(3), 6);
+    assert!(config.schedule(0).is_empty());
+    assert_eq!(config.frame_count(0), 0);
+}
@@ -0,0 +1,110 @@
+//! Still images of the window: file names, and high-resolution or transparent renders.
+
+use image::{Rgb, RgbImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use std::path::PathBuf;
+
+use misc;
+use misc::TemplateValue;
+
+#[cfg(test)]
+use toml;
+
+/// Configuration for saving snapshots
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// Size of the image, as a multiple of the window size. Above 1, the image is rendered in
+    /// tiles.
+    pub scale: u32,
+    /// Make the background transparent
+    pub transparent: bool,
+    /// Directory to save snapshots in; created if it does not exist
+    pub directory: String,
+    /// File name template, filled in like `text_format`, with `index` for the frame index
+    pub filename: String,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        SnapshotConfig {
+            scale: 1,
+            transparent: false,
+            directory: ".".into(),
+            filename: "frame{index:04}.png".into(),
+        }
+    }
+}
+
+impl SnapshotConfig {
+    /// Returns true if a snapshot needs more than a copy of the window
+    pub fn needs_render(&self) -> bool {
+        self.scale > 1 || self.transparent
+    }
+
+    /// The path to save a snapshot to, filling in the file name template.
+    pub fn path<F>(&self, lookup: F) -> PathBuf
+    where
+        F: Fn(&str) -> Option<TemplateValue>,
+    {
+        let filename = misc::format_template(&self.filename, lookup);
+        PathBuf::from(&self.directory).join(filename)
+    }
+}
+
+/// Recover a transparent image from the same scene rendered on black and on white.
+///
+/// Where the two differ, the background shows through: a pixel that is fully opaque is the
+/// same on both, and a fully transparent pixel is black on one and white on the other.
+pub fn unblend(on_black: &RgbImage, on_white: &RgbImage) -> RgbaImage {
+    RgbaImage::from_fn(on_black.width(), on_black.height(), |x, y| {
+        let Rgb(b) = *on_black.get_pixel(x, y);
+        let Rgb(w) = *on_white.get_pixel(x, y);
+        let difference: f32 = (0..3)
+            .map(|i| w[i].saturating_sub(b[i]) as f32)
+            .sum::<f32>()
+            / 3.;
+        let alpha = 1. - difference / 255.;
+        if alpha <= 0. {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let channel = |i: usize| (b[i] as f32 / alpha).round().min(255.) as u8;
+        Rgba([
+            channel(0),
+            channel(1),
+            channel(2),
+            (alpha * 255.).round() as u8,
+        ])
+    })
+}
+
+#[test]
+fn snapshot_unblend() {
+    let on_black = RgbImage::from_raw(3, 1, vec![200, 100, 0, 0, 0, 0, 100, 50, 0]).unwrap();
+    let on_white =
+        RgbImage::from_raw(3, 1, vec![200, 100, 0, 255, 255, 255, 228, 178, 128]).unwrap();
+    let image = unblend(&on_black, &on_white);
+    // Opaque
+    assert_eq!(image.get_pixel(0, 0), &Rgba([200, 100, 0, 255]));
+    // Background
+    assert_eq!(image.get_pixel(1, 0), &Rgba([0, 0, 0, 0]));
+    // Half transparent: the color is (200, 100, 0), to within rounding
+    assert_eq!(image.get_pixel(2, 0), &Rgba([201, 100, 0, 127]));
+}
+
+#[test]
+fn snapshot_path() {
+    let s = "
+        directory = \"figures\"
+        filename = \"run3-{index:05}-t{time:.1}.png\"
+    ";
+    let config: SnapshotConfig = toml::from_str(s).unwrap();
+    assert_eq!(config.scale, 1);
+    let path = config.path(|name| match name {
+        "index" => Some(TemplateValue::Number(42.)),
+        "time" => Some(TemplateValue::Number(1.5)),
+        _ => None,
+    });
+    assert_eq!(path, PathBuf::from("figures/run3-00042-t1.5.png"));
+}
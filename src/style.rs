@@ -0,0 +1,94 @@
+//! The look of the window: lighting and text.
+
+use na;
+
+use kiss3d::light::Light;
+use kiss3d::text::Font;
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fs;
+use std::rc::Rc;
+
+use palette::Color;
+
+#[cfg(test)]
+use toml;
+
+/// Where the scene is lit from.
+///
+/// In a config file, `light = "stick-to-camera"`, or `light = { absolute = [x, y, z] }`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "kebab-case")]
+pub enum LightMode {
+    /// A light at the camera, moving with it
+    StickToCamera,
+    /// A light fixed at a point in the scene
+    Absolute((f32, f32, f32)),
+}
+
+impl LightMode {
+    /// The kiss3d light
+    pub fn to_light(self) -> Light {
+        match self {
+            LightMode::StickToCamera => Light::StickToCamera,
+            LightMode::Absolute((x, y, z)) => Light::Absolute(na::Point3::new(x, y, z)),
+        }
+    }
+}
+
+/// How the frame text and the status line are drawn
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct TextStyle {
+    /// Font size
+    pub font_size: f32,
+    /// Text color
+    pub color: Color,
+    /// Top left corner of the frame text, in the window frame (0 to 1)
+    pub position: (f32, f32),
+    /// A TrueType font file to use instead of the default font
+    pub font: Option<String>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            font_size: 48.,
+            color: Color(255, 255, 255),
+            position: (0., 0.),
+            font: None,
+        }
+    }
+}
+
+impl TextStyle {
+    /// Load the configured font, or the default font if none is set.
+    pub fn load_font(&self) -> Result<Rc<Font>, Box<dyn Error>> {
+        match self.font {
+            None => Ok(Font::default()),
+            Some(ref path) => {
+                let bytes = fs::read(path)?;
+                let font = Font::from_bytes(&bytes)
+                    .ok_or_else(|| format!("Could not load the font '{}'", path))?;
+                Ok(font)
+            }
+        }
+    }
+}
+
+#[test]
+fn style_light_modes() {
+    #[derive(Deserialize)]
+    struct Lights {
+        camera: LightMode,
+        fixed: LightMode,
+    }
+    let s = "
+        camera = \"stick-to-camera\"
+        fixed = { absolute = [1.0, 2.0, 3.0] }
+    ";
+    let lights: Lights = toml::from_str(s).unwrap();
+    assert_eq!(lights.camera, LightMode::StickToCamera);
+    assert_eq!(lights.fixed, LightMode::Absolute((1., 2., 3.)));
+}
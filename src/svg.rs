@@ -0,0 +1,386 @@
+//! Vector-graphics export: project a frame through a camera, and write it as an SVG.
+//!
+//! This needs no window or GPU. The camera matches the `ArcBall` used by the viewer, so an
+//! export from `Parviewer::export_svg` looks like the window.
+
+use na;
+
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use camera::{CameraState, Projection, ZFAR, ZNEAR};
+use misc;
+use objects::{Frame, EPSILON};
+use palette::{Color, Palette};
+use parviewer::Config;
+
+/// A camera, an image size, and the other settings needed to draw a frame as an SVG.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgExport {
+    /// Camera position
+    pub camera: CameraState,
+    /// Camera projection
+    pub projection: Projection,
+    /// Width of the orthographic view
+    pub extent: f32,
+    /// Image width, in pixels
+    pub width: u32,
+    /// Image height, in pixels
+    pub height: u32,
+    /// Background color. None leaves the background transparent.
+    pub background: Option<Color>,
+    /// Draw the edges of the unit box
+    pub showbox: bool,
+}
+
+/// Something to draw, with its distance from the camera
+enum Shape {
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+        color: Color,
+    },
+    Capsule {
+        ends: ((f32, f32), (f32, f32)),
+        radius: f32,
+        color: Color,
+    },
+    Line {
+        ends: ((f32, f32), (f32, f32)),
+        width: f32,
+        color: Color,
+    },
+}
+
+impl SvgExport {
+    /// Settings matching the initial camera of a viewer with this config.
+    pub fn new(config: &Config) -> SvgExport {
+        SvgExport {
+            camera: config.initial_camera(),
+            projection: config.projection,
+            extent: config.ortho_extent(),
+            width: config.width,
+            height: config.height,
+            background: Some(config.background),
+            showbox: config.showbox,
+        }
+    }
+
+    fn projection_matrix(&self) -> na::Matrix4<f32> {
+        let aspect = self.width as f32 / self.height.max(1) as f32;
+        match self.projection {
+            Projection::Perspective => {
+                na::Perspective3::new(aspect, self.camera.fov * PI / 180., ZNEAR, ZFAR)
+                    .to_homogeneous()
+            }
+            Projection::Orthographic => {
+                let (w, h) = (self.extent / 2., self.extent / aspect / 2.);
+                na::Orthographic3::new(-w, w, -h, h, -ZFAR, ZFAR).to_homogeneous()
+            }
+        }
+    }
+
+    /// Project a point to the image, as (x, y, depth), with x and y in pixels from the top
+    /// left. Returns None for points behind the camera.
+    fn project(
+        &self,
+        view: &na::Isometry3<f32>,
+        proj: &na::Matrix4<f32>,
+        point: &na::Point3<f32>,
+    ) -> Option<(f32, f32, f32)> {
+        let camera_point = view * point;
+        let depth = -camera_point.z;
+        if self.projection == Projection::Perspective && depth <= ZNEAR {
+            return None;
+        }
+        let clip = proj * camera_point.to_homogeneous();
+        if clip.w.abs() < EPSILON {
+            return None;
+        }
+        let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+        Some((
+            (x + 1.) / 2. * self.width as f32,
+            (1. - y) / 2. * self.height as f32,
+            depth,
+        ))
+    }
+
+    /// Draw a frame as an SVG document. Colors come from the palette, as in the viewer.
+    pub fn render(&self, frame: &Frame, palette: &mut Palette) -> String {
+        let view = self.camera.view_transform();
+        let proj = self.projection_matrix();
+        // A unit vector across the screen, for the projected size of things
+        let right = view.inverse() * na::Vector3::x();
+        let project = |p: na::Point3<f32>| self.project(&view, &proj, &p);
+        let projected_radius = |p: na::Point3<f32>, (x, y): (f32, f32), r: f32| {
+            project(p + right * r).map(|(rx, ry, _)| ((rx - x).powi(2) + (ry - y).powi(2)).sqrt())
+        };
+
+        let mut shapes: Vec<(f32, Shape)> = vec![];
+        for sphere in &frame.spheres {
+            let center = na::Point3::from(sphere.x());
+            if let Some((x, y, depth)) = project(center) {
+                let radius = projected_radius(center, (x, y), sphere.diameter / 2.);
+                let color = palette.get_color(&sphere.names);
+                shapes.push((
+                    depth,
+                    Shape::Circle {
+                        center: (x, y),
+                        radius: radius.unwrap_or(0.),
+                        color,
+                    },
+                ));
+            }
+        }
+
+        for spherocylinder in &frame.spherocylinders {
+            let center = na::Point3::from(spherocylinder.x());
+            let axis = spherocylinder.get_axis();
+            let length = axis.norm();
+            let diameter = spherocylinder.diameter;
+            // The axis runs from cap to cap; the caps are centred a radius in from each end
+            let half = if length > diameter {
+                axis * ((length - diameter) / (2. * length))
+            } else {
+                na::Vector3::zeros()
+            };
+            if let (Some(a), Some(b), Some(c)) = (
+                project(center - half),
+                project(center + half),
+                project(center),
+            ) {
+                let radius = projected_radius(center, (c.0, c.1), diameter / 2.);
+                let color = palette.get_color(&spherocylinder.names);
+                shapes.push((
+                    c.2,
+                    Shape::Capsule {
+                        ends: ((a.0, a.1), (b.0, b.1)),
+                        radius: radius.unwrap_or(0.),
+                        color,
+                    },
+                ));
+            }
+        }
+
+        if self.showbox {
+            let red = Color(255, 0, 0);
+            for &(a, b) in &misc::box_edges() {
+                let middle = na::center(&a, &b);
+                if let (Some(pa), Some(pb), Some(pm)) = (project(a), project(b), project(middle)) {
+                    let width = projected_radius(middle, (pm.0, pm.1), misc::BOX_EDGE_RADIUS);
+                    shapes.push((
+                        pm.2,
+                        Shape::Line {
+                            ends: ((pa.0, pa.1), (pb.0, pb.1)),
+                            width: 2. * width.unwrap_or(0.).max(0.5),
+                            color: red,
+                        },
+                    ));
+                }
+            }
+        }
+
+        // Painter's algorithm: the farthest first
+        shapes.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        self.document(shapes.into_iter().map(|(_, shape)| shape).collect())
+    }
+
+    fn document(&self, shapes: Vec<Shape>) -> String {
+        let mut svg = String::new();
+        let (w, h) = (self.width, self.height);
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            w, h, w, h
+        );
+
+        // One shading gradient per sphere color
+        let colors: BTreeSet<Color> = shapes
+            .iter()
+            .filter_map(|shape| match *shape {
+                Shape::Circle { color, .. } => Some(color),
+                _ => None,
+            })
+            .collect();
+        let _ = writeln!(svg, "<defs>");
+        for &color in &colors {
+            let _ = writeln!(
+                svg,
+                r#"<radialGradient id="shade-{}" cx="0.5" cy="0.5" r="0.5" fx="0.35" fy="0.35">"#,
+                hex(color)
+            );
+            let _ = writeln!(
+                svg,
+                r#"<stop offset="0" stop-color="{}"/><stop offset="0.6" stop-color="{}"/><stop offset="1" stop-color="{}"/>"#,
+                hex_color(mix(color, Color(255, 255, 255), 0.6)),
+                hex_color(color),
+                hex_color(mix(color, Color(0, 0, 0), 0.4))
+            );
+            let _ = writeln!(svg, "</radialGradient>");
+        }
+        let _ = writeln!(svg, "</defs>");
+
+        if let Some(background) = self.background {
+            let _ = writeln!(
+                svg,
+                r#"<rect width="100%" height="100%" fill="{}"/>"#,
+                hex_color(background)
+            );
+        }
+
+        let outline = r##"stroke="#000000" stroke-opacity="0.5" stroke-width="0.5""##;
+        for shape in shapes {
+            let _ = match shape {
+                Shape::Circle {
+                    center: (x, y),
+                    radius,
+                    color,
+                } => writeln!(
+                    svg,
+                    r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="url(#shade-{})" {}/>"#,
+                    x,
+                    y,
+                    radius,
+                    hex(color),
+                    outline
+                ),
+                Shape::Capsule {
+                    ends: ((x1, y1), (x2, y2)),
+                    radius,
+                    color,
+                } => {
+                    // A round-capped line, over a slightly wider dark one for the outline
+                    let line = |width: f32, color: &str, opacity: f32| {
+                        format!(
+                            r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-opacity="{}" stroke-width="{:.2}" stroke-linecap="round"/>"#,
+                            x1, y1, x2, y2, color, opacity, width
+                        )
+                    };
+                    writeln!(
+                        svg,
+                        "{}\n{}",
+                        line(2. * radius + 1., "#000000", 0.5),
+                        line(2. * radius, &hex_color(color), 1.)
+                    )
+                }
+                Shape::Line {
+                    ends: ((x1, y1), (x2, y2)),
+                    width,
+                    color,
+                } => writeln!(
+                    svg,
+                    r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="{:.2}" stroke-linecap="round"/>"#,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    hex_color(color),
+                    width
+                ),
+            };
+        }
+        let _ = writeln!(svg, "</svg>");
+        svg
+    }
+
+    /// Draw a frame, and save it to a file.
+    pub fn save(
+        &self,
+        frame: &Frame,
+        palette: &mut Palette,
+        path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.render(frame, palette))?;
+        Ok(())
+    }
+}
+
+/// A color as six hex digits, e.g. "ff8000"
+fn hex(color: Color) -> String {
+    let Color(r, g, b) = color;
+    format!("{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// A color for SVG attributes, e.g. "#ff8000"
+fn hex_color(color: Color) -> String {
+    format!("#{}", hex(color))
+}
+
+/// Move a color a fraction of the way towards another
+fn mix(color: Color, other: Color, fraction: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    Color(
+        channel(color.0, other.0),
+        channel(color.1, other.1),
+        channel(color.2, other.2),
+    )
+}
+
+#[cfg(test)]
+use objects::{ObjectID, Sphere};
+
+#[test]
+fn svg_depth_order() {
+    let sphere = |z: f32, name: &str| Sphere {
+        loc: (0., 0., z),
+        diameter: 0.2,
+        names: ObjectID(vec![name.into()]),
+        vectors: Default::default(),
+    };
+    let frame = Frame {
+        spheres: vec![sphere(0.3, "A"), sphere(-0.3, "B")],
+        spherocylinders: vec![],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: Default::default(),
+        forces: vec![],
+    };
+    let mut palette = Palette::default();
+    let color_a = palette.get_color(&ObjectID(vec!["A".into()]));
+    let color_b = palette.get_color(&ObjectID(vec!["B".into()]));
+
+    // Looking from +z, "B" is behind "A", so it is drawn first
+    let mut export = SvgExport {
+        camera: CameraState {
+            yaw: 90.,
+            pitch: 90.,
+            distance: 2.,
+            fov: 45.,
+            target: (0., 0., 0.),
+        },
+        projection: Projection::Perspective,
+        extent: 1.,
+        width: 400,
+        height: 300,
+        background: None,
+        showbox: false,
+    };
+    let svg = export.render(&frame, &mut palette);
+    let a = svg.find(&format!("shade-{})", hex(color_a))).unwrap();
+    let b = svg.find(&format!("shade-{})", hex(color_b))).unwrap();
+    assert!(b < a);
+    // Both are at the centre of the image, and the nearer one looks bigger
+    assert_eq!(svg.matches(r#"cx="200.00" cy="150.00""#).count(), 2);
+    assert!(svg.contains(r#"r="21.30""#));
+    assert!(svg.contains(r#"r="15.74""#));
+
+    // From -z, the order is reversed; orthographic spheres are all the same size
+    export.camera.yaw = -90.;
+    export.projection = Projection::Orthographic;
+    export.showbox = true;
+    let svg = export.render(&frame, &mut palette);
+    let a = svg.find(&format!("shade-{})", hex(color_a))).unwrap();
+    let b = svg.find(&format!("shade-{})", hex(color_b))).unwrap();
+    assert!(a < b);
+    assert_eq!(svg.matches(r#"r="40.00""#).count(), 2);
+    assert_eq!(svg.matches("<line").count(), 12);
+}
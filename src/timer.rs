@@ -9,12 +9,26 @@ pub struct Timer {
     dti: isize,    /* which of dts we're talking about. 0 is stop, 1 => dts[0], -1 =>
                     * -dts[0] */
     len: Option<usize>, // length of what we're iterating over
-    /// Current index, as float; keeps track of partials
+    /// Current position, as float, counted in steps of `stride` from `start`; keeps track of
+    /// partials
     pub t: f32,
+    /// First index of the playback range
+    pub start: usize,
+    /// End of the playback range (exclusive). None means "play to the end".
+    pub end: Option<usize>,
+    /// Only show every `stride`-th index
+    pub stride: usize,
     /// Time to pause before re-looping. None means "don't loop".
     pub loop_pause: Option<f32>,
-    /// Maximum rate
+    /// Reverse direction at the ends of the range instead of wrapping around. Pauses for
+    /// `loop_pause` (if any) at each end.
+    pub pingpong: bool,
+    /// Rendering rate, in ticks per second. Playback speed is set separately, by `get_dt()`.
     pub fps: f32,
+    /// Simulation time units per second of playback at speed 1. When set, and frame times are
+    /// known, playback advances in simulation time rather than in frames.
+    pub timescale: Option<f32>,
+    times: Vec<f32>, // simulation time of each index; empty if unknown
 }
 
 impl Timer {
@@ -28,8 +42,14 @@ impl Timer {
             dti: 1,
             len,
             t: 0.0,
+            start: 0,
+            end: None,
+            stride: 1,
             loop_pause: None,
+            pingpong: false,
             fps: 1.0,
+            timescale: None,
+            times: vec![],
         }
     }
 
@@ -91,11 +111,114 @@ impl Timer {
         self.t
     }
 
+    /// Set the simulation time of each index, for real-time playback. Times should be
+    /// increasing.
+    pub fn set_frame_times(&mut self, times: Vec<f32>) {
+        self.times = times;
+    }
+
+    /// Current simulation time, interpolated between frames. None if frame times are unknown.
+    pub fn sim_time(&self) -> Option<f32> {
+        if self.times.is_empty() {
+            return None;
+        }
+        let last = self.steps().unwrap_or(self.times.len()).max(1) - 1;
+        Some(self.step_time(self.t.max(0.).min(last as f32)))
+    }
+
+    /// Simulation time of a given step
+    fn frame_time(&self, step: usize) -> f32 {
+        let ix = self.range_start() + self.stride.max(1) * step;
+        self.times[ix.min(self.times.len() - 1)]
+    }
+
+    /// Convert a (fractional) step to a simulation time, interpolating linearly between frames,
+    /// and extrapolating past the ends.
+    fn step_time(&self, step: f32) -> f32 {
+        let n = self.steps().unwrap_or(self.times.len());
+        if n < 2 {
+            return self.frame_time(0) + step;
+        }
+        let i = (step.max(0.) as usize).min(n - 2);
+        let (t0, t1) = (self.frame_time(i), self.frame_time(i + 1));
+        t0 + (step - i as f32) * (t1 - t0)
+    }
+
+    /// Convert a simulation time to a (fractional) step; the inverse of `step_time`.
+    fn time_step(&self, time: f32) -> f32 {
+        let n = self.steps().unwrap_or(self.times.len());
+        if n < 2 {
+            return time - self.frame_time(0);
+        }
+        // Find the last step (excluding the final one) at or before `time`
+        let (mut lo, mut hi) = (0, n - 2);
+        while lo < hi {
+            let mid = (lo + hi + 1) / 2;
+            if self.frame_time(mid) <= time {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        let (t0, t1) = (self.frame_time(lo), self.frame_time(lo + 1));
+        if t1 > t0 {
+            lo as f32 + (time - t0) / (t1 - t0)
+        } else {
+            lo as f32 + 1.
+        }
+    }
+
+    /// Number of steps in the playback range, taking `start`, `end`, and `stride` into account.
+    /// None if the length is unknown.
+    pub fn steps(&self) -> Option<usize> {
+        let end = self.range_end()?;
+        let start = self.range_start();
+        let stride = self.stride.max(1);
+        Some(((end - start + stride - 1) / stride).max(1))
+    }
+
+    /// End of the playback range (exclusive), limited by the length
+    fn range_end(&self) -> Option<usize> {
+        match (self.len, self.end) {
+            (Some(len), Some(end)) => Some(len.min(end)),
+            (Some(len), None) => Some(len),
+            (None, end) => end,
+        }
+    }
+
+    /// Start of the playback range, limited to lie within the range
+    fn range_start(&self) -> usize {
+        match self.range_end() {
+            Some(end) => self.start.min(end.saturating_sub(1)),
+            None => self.start,
+        }
+    }
+
     /// Increment the timer, and return current index
     pub fn incr(&mut self) {
-        self.t += self.get_dt() / self.fps;
+        let delta = self.get_dt() / self.fps;
+        self.t = match self.timescale {
+            Some(scale) if !self.times.is_empty() => {
+                // Advance in simulation time, so unevenly spaced frames play at the right pace
+                let now = self.step_time(self.t);
+                self.time_step(now + delta * scale)
+            }
+            _ => self.t + delta,
+        };
 
-        match (self.len, self.loop_pause) {
+        match (self.steps(), self.loop_pause) {
+            (Some(n), pause) if self.pingpong => {
+                // Bounce back and forth, pausing at either end
+                let pause = pause.unwrap_or(0.);
+                let (lo, hi) = (-pause, (n - 1) as f32 + pause);
+                if self.t > hi {
+                    self.t = (2. * hi - self.t).max(lo);
+                    self.switch_direction();
+                } else if self.t < lo {
+                    self.t = (2. * lo - self.t).min(hi);
+                    self.switch_direction();
+                }
+            }
             (None, _) if self.t < 0. => {
                 self.t = 0.;
             }
@@ -103,58 +226,104 @@ impl Timer {
                 // fixed length, but no loop, but t is negative
                 self.t = 0.;
             }
-            (Some(len), Some(pause)) => {
+            (Some(n), Some(pause)) => {
                 // We have a fixed length, but we loop after a pause.
-                let loop_len = (len as f32) + pause;
-                if self.t < 0. {
-                    self.t += loop_len;
-                } else if self.t > loop_len {
-                    self.t -= loop_len;
+                let loop_len = (n as f32) + pause;
+                if self.t < 0. || self.t > loop_len {
+                    self.t = self.t.rem_euclid(loop_len);
                 };
             }
             _ => {}
         }
     }
 
-    /// Total time before looping
+    /// Total time before looping. For ping-pong playback, this is a full cycle there and back.
     pub fn total_loop_time(&self) -> Option<f32> {
-        match (self.len, self.loop_pause) {
+        match (self.steps(), self.loop_pause) {
             (None, _) => None,
-            (Some(len), None) => Some(len as f32),
-            (Some(len), Some(pause)) => Some((len as f32) + pause),
+            (Some(n), pause) if self.pingpong => {
+                Some(2. * ((n - 1) as f32 + 2. * pause.unwrap_or(0.)))
+            }
+            (Some(n), None) => Some(n as f32),
+            (Some(n), Some(pause)) => Some((n as f32) + pause),
         }
     }
 
-    /// Get the current index into the array
-    pub fn get_index(&self) -> usize {
-        match (self.len, self.loop_pause) {
-            (None, _) if self.t < 0. => 0,
+    /// Get the current step within the playback range
+    fn get_step(&self) -> usize {
+        match (self.steps(), self.loop_pause) {
+            (_, _) if self.t < 0. => 0,
             (None, _) => self.t as usize,
-            (_, None) if self.t < 0. => {
-                // fixed length, but no loop, but t is negative
-                0
+            (Some(n), Some(pause)) if !self.pingpong => {
+                // We have a fixed length, and we loop after a pause.
+                let loop_len = (n as f32) + pause;
+                let ix = (self.t % loop_len) as usize;
+                ix.min(n - 1)
             }
-            (Some(len), None) => {
-                // We have a fixed length, but we don't loop.
-                let ix = self.t as usize;
-                if ix >= len {
-                    len - 1
-                } else {
-                    ix
-                }
+            (Some(n), _) => {
+                // We have a fixed length, and either don't loop or bounce back and forth;
+                // stay on the last frame when past the end.
+                (self.t as usize).min(n - 1)
             }
-            (Some(len), Some(pause)) => {
-                // We have a fixed length, but we don't loop.
-                let loop_len = (len as f32) + pause;
-                let ix = (self.t % loop_len) as usize;
-                if ix >= len {
-                    len - 1
-                } else {
-                    ix
-                }
+        }
+    }
+
+    /// Get the current (fractional) step within the playback range
+    fn get_step_position(&self) -> f32 {
+        match (self.steps(), self.loop_pause) {
+            (_, _) if self.t < 0. => 0.,
+            (None, _) => self.t,
+            (Some(n), Some(pause)) if !self.pingpong => {
+                (self.t % ((n as f32) + pause)).min((n - 1) as f32)
             }
+            (Some(n), _) => self.t.min((n - 1) as f32),
+        }
+    }
+
+    /// Get the current (fractional) position in the array, for smooth motion between indices
+    pub fn get_position(&self) -> f32 {
+        self.range_start() as f32 + self.stride.max(1) as f32 * self.get_step_position()
+    }
+
+    /// The index after the current one in the playback range, and how far (0 to 1) the current
+    /// position is towards it, for interpolating between frames. None if the position is on an
+    /// index, or at the end of the range.
+    pub fn get_next(&self) -> Option<(usize, f32)> {
+        let step = self.get_step_position();
+        let fraction = step - step.floor();
+        let next = self.get_index() + self.stride.max(1);
+        match self.range_end() {
+            Some(end) if next >= end => None,
+            _ if fraction <= 0. => None,
+            _ => Some((next, fraction)),
         }
     }
+
+    /// Jump to the step nearest to a given index, within the playback range
+    pub fn seek(&mut self, index: usize) {
+        let start = self.range_start();
+        let stride = self.stride.max(1);
+        let step = (index.saturating_sub(start) + stride / 2) / stride;
+        let step = match self.steps() {
+            Some(n) => step.min(n - 1),
+            None => step,
+        };
+        self.t = step as f32;
+    }
+
+    /// Show exactly `index`, whatever the playback range and stride, by making it the only
+    /// step of the range
+    pub fn show_index(&mut self, index: usize) {
+        self.start = index;
+        self.end = Some(index + 1);
+        self.stride = 1;
+        self.t = 0.;
+    }
+
+    /// Get the current index into the array
+    pub fn get_index(&self) -> usize {
+        self.range_start() + self.stride.max(1) * self.get_step()
+    }
 }
 
 #[cfg(test)]
@@ -231,4 +400,109 @@ mod test {
         t.incr();
         assert_eq!(t.get_index(), 0); // t = 10.
     }
+
+    #[test]
+    fn timer_range_stride() {
+        let mut t = ::Timer::new(vec![1.], Some(500));
+        t.start = 200;
+        t.end = Some(451);
+        t.stride = 5;
+        assert_eq!(t.steps(), Some(51));
+        assert_eq!(t.get_index(), 200);
+        t.incr();
+        assert_eq!(t.get_index(), 205);
+        t.t = 50.;
+        assert_eq!(t.get_index(), 450);
+        t.incr();
+        assert_eq!(t.get_index(), 450); // no loop, so we stay at the end
+        t.loop_pause = Some(0.);
+        t.t = 50.;
+        t.incr();
+        assert_eq!(t.get_index(), 200); // t = 51., which wraps to 0.
+        t.switch_direction();
+        t.incr();
+        assert_eq!(t.get_index(), 450); // t = 50.
+    }
+
+    #[test]
+    fn timer_pingpong() {
+        let mut t = ::Timer::new(vec![1.], Some(10));
+        t.start = 2;
+        t.end = Some(5);
+        t.pingpong = true;
+        let mut indices = vec![t.get_index()];
+        for _ in 0..8 {
+            t.incr();
+            indices.push(t.get_index());
+        }
+        assert_eq!(indices, vec![2, 3, 4, 3, 2, 3, 4, 3, 2]);
+        assert_eq!(t.total_loop_time(), Some(4.));
+
+        let mut t = ::Timer::new(vec![1.], Some(3));
+        t.loop_pause = Some(1.);
+        t.pingpong = true;
+        let mut indices = vec![t.get_index()];
+        for _ in 0..8 {
+            t.incr();
+            indices.push(t.get_index());
+        }
+        assert_eq!(indices, vec![0, 1, 2, 2, 2, 1, 0, 0, 0]);
+        assert_eq!(t.total_loop_time(), Some(8.));
+    }
+
+    #[test]
+    fn timer_seek() {
+        let mut t = ::Timer::new(vec![1.], Some(100));
+        t.seek(42);
+        assert_eq!(t.get_index(), 42);
+        t.start = 10;
+        t.end = Some(50);
+        t.stride = 4;
+        t.seek(42);
+        assert_eq!(t.get_index(), 42);
+        t.seek(43);
+        assert_eq!(t.get_index(), 42);
+        t.seek(0);
+        assert_eq!(t.get_index(), 10);
+        t.seek(99);
+        assert_eq!(t.get_index(), 46);
+        t.t = 2.5;
+        assert_eq!(t.get_position(), 20.);
+        assert_eq!(t.get_next(), Some((22, 0.5)));
+        t.t = 2.;
+        assert_eq!(t.get_next(), None);
+        t.t = 9.25;
+        assert_eq!(t.get_index(), 46);
+        assert_eq!(t.get_next(), None); // past the last index
+
+        // Outside the range, and off the stride
+        t.show_index(3);
+        assert_eq!(t.get_index(), 3);
+        assert_eq!(t.get_position(), 3.);
+        assert_eq!(t.get_next(), None);
+    }
+
+    #[test]
+    fn timer_realtime() {
+        let mut t = ::Timer::new(vec![1.], Some(5));
+        t.set_frame_times(vec![0., 1., 2., 6., 10.]);
+        t.timescale = Some(2.);
+        t.fps = 2.;
+        assert_eq!(t.sim_time(), Some(0.));
+        t.incr();
+        assert_eq!(t.get_index(), 1); // time = 1.
+        t.incr();
+        assert_eq!(t.get_index(), 2); // time = 2.
+        t.incr();
+        assert_eq!(t.get_index(), 2); // time = 3.
+        assert_eq!(t.sim_time(), Some(3.));
+        t.incr();
+        t.incr();
+        assert_eq!(t.get_index(), 2); // time = 5.
+        t.incr();
+        assert_eq!(t.get_index(), 3); // time = 6.
+        t.switch_direction();
+        t.incr();
+        assert_eq!(t.get_index(), 2); // time = 5.
+    }
 }
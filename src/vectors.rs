@@ -0,0 +1,141 @@
+//! Per-particle vectors, such as velocities and forces, drawn as arrows from the particle
+//! centres.
+
+use serde::{Deserialize, Serialize};
+
+use objects::{Arrow, Frame, ObjectID};
+use palette;
+use palette::Color;
+
+#[cfg(test)]
+use objects::Sphere;
+#[cfg(test)]
+use std::collections::BTreeMap;
+#[cfg(test)]
+use toml;
+
+/// How vectors are drawn
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct VectorConfig {
+    /// The vector shown when the viewer starts. None shows no vectors until one is chosen
+    /// with the A key.
+    pub show: Option<String>,
+    /// Length of an arrow, per unit of the vector
+    pub scale: f32,
+    /// Diameter of the arrow shafts
+    pub width: f32,
+    /// Magnitude at the top of the color scale. None uses the largest in each frame.
+    pub max: Option<f32>,
+    /// Colors from no magnitude up to `max`, evenly spaced
+    pub colors: Vec<Color>,
+}
+
+impl Default for VectorConfig {
+    fn default() -> Self {
+        VectorConfig {
+            show: None,
+            scale: 1.,
+            width: 0.02,
+            max: None,
+            colors: vec![
+                Color(68, 1, 84),
+                Color(59, 82, 139),
+                Color(33, 145, 140),
+                Color(94, 201, 98),
+                Color(253, 231, 37),
+            ],
+        }
+    }
+}
+
+impl VectorConfig {
+    /// An arrow for the vector called `name` of each object in `frame` that has one, colored
+    /// by its magnitude.
+    pub fn arrows(&self, frame: &Frame, name: &str) -> Vec<Arrow> {
+        let spheres = frame
+            .spheres
+            .iter()
+            .filter_map(|s| s.vectors.get(name).map(|&v| (&s.names, s.loc, v)));
+        let spherocylinders = frame
+            .spherocylinders
+            .iter()
+            .filter_map(|s| s.vectors.get(name).map(|&v| (&s.names, s.loc, v)));
+        let vectors: Vec<_> = spheres.chain(spherocylinders).collect();
+
+        let magnitude = |(x, y, z): (f32, f32, f32)| (x * x + y * y + z * z).sqrt();
+        let max = self.max.unwrap_or_else(|| {
+            vectors
+                .iter()
+                .map(|&(_, _, v)| magnitude(v))
+                .fold(0., f32::max)
+        });
+        vectors
+            .into_iter()
+            .map(|(names, loc, v)| {
+                // Named after the object, so each arrow follows its object from frame to frame
+                let mut names = names.0.clone();
+                names.push(format!("{} vector", name));
+                let (x, y, z) = v;
+                Arrow {
+                    loc,
+                    vector: (x * self.scale, y * self.scale, z * self.scale),
+                    width: self.width,
+                    color: palette::colormap(&self.colors, magnitude(v), max),
+                    names: ObjectID(names),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The vector to show after `current`, out of `names`: the next one, then none after the
+/// last, then the first again.
+pub fn next_vector(names: &[String], current: Option<&str>) -> Option<String> {
+    let next = match current.and_then(|c| names.iter().position(|n| n == c)) {
+        Some(i) => i + 1,
+        None if current.is_some() => return names.first().cloned(),
+        None => 0,
+    };
+    names.get(next).cloned()
+}
+
+#[test]
+fn vector_arrows() {
+    let sphere = |name: &str, velocity: (f32, f32, f32)| Sphere {
+        loc: (1., 2., 3.),
+        diameter: 1.,
+        names: ObjectID(vec![name.into()]),
+        vectors: vec![("velocity".into(), velocity)].into_iter().collect(),
+    };
+    let mut still = sphere("c", (0., 0., 0.));
+    still.vectors.clear();
+    let frame = Frame {
+        spheres: vec![sphere("a", (0., 3., 4.)), sphere("b", (0., 1., 0.)), still],
+        spherocylinders: vec![],
+        text: String::new(),
+        time: None,
+        step: None,
+        values: BTreeMap::new(),
+        forces: vec![],
+    };
+    assert_eq!(frame.vector_names(), vec!["velocity".to_string()]);
+
+    let config: VectorConfig =
+        toml::from_str("scale = 0.5\ncolors = [[0, 0, 0], [250, 250, 250]]").unwrap();
+    let arrows = config.arrows(&frame, "velocity");
+    assert_eq!(arrows.len(), 2);
+    assert_eq!(arrows[0].loc, (1., 2., 3.));
+    assert_eq!(arrows[0].vector, (0., 1.5, 2.));
+    assert_eq!(arrows[0].color, Color(250, 250, 250));
+    assert_eq!(arrows[1].color, Color(50, 50, 50));
+    assert_eq!(arrows[1].names.0, vec!["b", "velocity vector"]);
+    assert!(config.arrows(&frame, "force").is_empty());
+
+    let names = vec!["force".to_string(), "velocity".to_string()];
+    assert_eq!(next_vector(&names, None), Some("force".into()));
+    assert_eq!(next_vector(&names, Some("force")), Some("velocity".into()));
+    assert_eq!(next_vector(&names, Some("velocity")), None);
+    assert_eq!(next_vector(&names, Some("gone")), Some("force".into()));
+    assert_eq!(next_vector(&[], None), None);
+}
@@ -0,0 +1,711 @@
+//! Video encoders for `pvrecord`: an external `ffmpeg` process, PNG sequences, animated GIF
+//! and APNG, and (with the `mpeg` feature) the old `mpeg_encoder` library.
+
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use gif;
+use gif::SetParameter;
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+
+#[cfg(feature = "mpeg")]
+use image::imageops;
+#[cfg(feature = "mpeg")]
+use mpeg_encoder;
+
+use misc;
+use misc::TemplateValue;
+
+#[cfg(test)]
+use toml;
+
+/// How a recording is written
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum VideoFormat {
+    /// Raw frames piped to an external `ffmpeg` process, for e.g. `.mp4`, `.mkv` or `.webm`
+    Ffmpeg,
+    /// One PNG file per frame
+    Png,
+    /// An animated GIF
+    Gif,
+    /// An animated PNG
+    Apng,
+    /// The `mpeg_encoder` library; needs the `mpeg` feature
+    Mpeg,
+}
+
+impl VideoFormat {
+    /// The format for a file name, by its extension
+    pub fn from_path(path: &Path) -> Option<VideoFormat> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        let format = match &ext[..] {
+            "png" => VideoFormat::Png,
+            "gif" => VideoFormat::Gif,
+            "apng" => VideoFormat::Apng,
+            "mpg" | "mpeg" if cfg!(feature = "mpeg") => VideoFormat::Mpeg,
+            _ => VideoFormat::Ffmpeg,
+        };
+        Some(format)
+    }
+}
+
+impl FromStr for VideoFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<VideoFormat, String> {
+        match s {
+            "ffmpeg" => Ok(VideoFormat::Ffmpeg),
+            "png" => Ok(VideoFormat::Png),
+            "gif" => Ok(VideoFormat::Gif),
+            "apng" => Ok(VideoFormat::Apng),
+            "mpeg" => Ok(VideoFormat::Mpeg),
+            _ => Err(format!(
+                "Unknown video format '{}'; use ffmpeg, png, gif, apng or mpeg",
+                s
+            )),
+        }
+    }
+}
+
+/// Configuration for recording videos
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct VideoConfig {
+    /// Output format. None chooses by the file extension.
+    pub format: Option<VideoFormat>,
+    /// The `ffmpeg` executable
+    pub ffmpeg: String,
+    /// Video codec for `ffmpeg`, e.g. "libx264" or "libvpx-vp9". None lets `ffmpeg` choose for
+    /// the container.
+    pub codec: Option<String>,
+    /// Constant rate factor for `ffmpeg`: lower is better quality, and larger
+    pub crf: Option<u32>,
+    /// Pixel format of the encoded video; "yuv420p" plays almost everywhere
+    pub pixel_format: String,
+    /// More `ffmpeg` output options, e.g. ["-preset", "slow"]
+    pub args: Vec<String>,
+    /// How many times GIF and APNG animations play; 0 loops forever
+    pub loops: u16,
+}
+
+impl Default for VideoConfig {
+    fn default() -> Self {
+        VideoConfig {
+            format: None,
+            ffmpeg: "ffmpeg".into(),
+            codec: None,
+            crf: None,
+            pixel_format: "yuv420p".into(),
+            args: vec![],
+            loops: 0,
+        }
+    }
+}
+
+/// Writes frames to a video, or a series of images
+pub trait VideoEncoder {
+    /// Add a frame. All frames must be the same size.
+    fn add_frame(&mut self, image: &RgbImage) -> Result<(), Box<dyn Error>>;
+
+    /// Finish writing. No frames may be added after this.
+    fn finish(&mut self) -> Result<(), Box<dyn Error>>;
+
+    /// Describe how the frames are made, such as the config and window size, so that `resume`
+    /// only continues a recording made the same way. Only PNG sequences keep it.
+    fn set_settings(&mut self, _settings: &str) {}
+
+    /// Continue an interrupted recording, returning how many of its frames to skip. Only PNG
+    /// sequences can be resumed, and only if they were recorded with the same settings.
+    fn resume(&mut self) -> Result<usize, Box<dyn Error>> {
+        Err("Only PNG sequences can be resumed".into())
+    }
+}
+
+impl VideoConfig {
+    /// Arguments for `ffmpeg` to read raw frames from its input, and encode them to `path`
+    pub fn ffmpeg_args(&self, width: u32, height: u32, fps: f32, path: &Path) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "-y".into(),
+            "-loglevel".into(),
+            "error".into(),
+            "-f".into(),
+            "rawvideo".into(),
+            "-pixel_format".into(),
+            "rgb24".into(),
+            "-video_size".into(),
+            format!("{}x{}", width, height),
+            "-framerate".into(),
+            format!("{}", fps),
+            "-i".into(),
+            "-".into(),
+        ];
+        if let Some(ref codec) = self.codec {
+            args.extend(vec!["-c:v".into(), codec.clone()]);
+        }
+        if let Some(crf) = self.crf {
+            args.extend(vec!["-crf".into(), format!("{}", crf)]);
+        }
+        args.extend(vec!["-pix_fmt".into(), self.pixel_format.clone()]);
+        // Most codecs need an even width and height, so the frames are padded after any
+        // filters given in `args`
+        let pad = "pad=ceil(iw/2)*2:ceil(ih/2)*2";
+        let mut extra = self.args.clone();
+        let filters = extra
+            .iter()
+            .position(|arg| arg == "-vf" || arg == "-filter:v")
+            .map(|i| i + 1)
+            .filter(|&i| i < extra.len());
+        match filters {
+            Some(i) => extra[i] = format!("{},{}", extra[i], pad),
+            None => args.extend(vec!["-vf".into(), pad.into()]),
+        }
+        args.extend(extra);
+        args.push(path.to_string_lossy().into_owned());
+        args
+    }
+
+    /// Start writing a video to `path`, at `fps` frames per second. `format` overrides the
+    /// configured format and the file extension.
+    pub fn open(
+        &self,
+        path: &Path,
+        fps: f32,
+        format: Option<VideoFormat>,
+    ) -> Result<Box<dyn VideoEncoder>, Box<dyn Error>> {
+        let format = format
+            .or(self.format)
+            .or_else(|| VideoFormat::from_path(path))
+            .ok_or_else(|| format!("No video format for '{}'", path.display()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let encoder: Box<dyn VideoEncoder> = match format {
+            VideoFormat::Ffmpeg => Box::new(FfmpegEncoder {
+                config: self.clone(),
+                path: path.to_path_buf(),
+                fps,
+                child: None,
+                size: None,
+            }),
+            VideoFormat::Png => Box::new(PngSequence::new(path)),
+            VideoFormat::Gif => Box::new(GifEncoder {
+                file: Some(BufWriter::new(File::create(path)?)),
+                encoder: None,
+                // In hundredths of a second; many viewers treat less than 2 as 10
+                delay: ((100. / fps).round() as u16).max(2),
+                loops: self.loops,
+                size: None,
+            }),
+            VideoFormat::Apng => Box::new(ApngEncoder::new(path, fps, self.loops)?),
+            VideoFormat::Mpeg => mpeg_encoder(path, fps)?,
+        };
+        Ok(encoder)
+    }
+}
+
+/// Check that a frame is the same size as the first
+fn check_size(size: &mut Option<(u32, u32)>, image: &RgbImage) -> Result<(), Box<dyn Error>> {
+    let new_size = image.dimensions();
+    match *size {
+        None => *size = Some(new_size),
+        Some(old_size) if old_size != new_size => {
+            let msg = format!(
+                "The frame size changed from {}x{} to {}x{}; do not resize the window while recording",
+                old_size.0, old_size.1, new_size.0, new_size.1
+            );
+            return Err(msg.into());
+        }
+        Some(_) => {}
+    }
+    Ok(())
+}
+
+/// Pipes raw frames to `ffmpeg`, started on the first frame
+struct FfmpegEncoder {
+    config: VideoConfig,
+    path: PathBuf,
+    fps: f32,
+    child: Option<Child>,
+    size: Option<(u32, u32)>,
+}
+
+impl VideoEncoder for FfmpegEncoder {
+    fn add_frame(&mut self, image: &RgbImage) -> Result<(), Box<dyn Error>> {
+        check_size(&mut self.size, image)?;
+        if self.child.is_none() {
+            let (width, height) = image.dimensions();
+            let args = self.config.ffmpeg_args(width, height, self.fps, &self.path);
+            let child = Command::new(&self.config.ffmpeg)
+                .args(&args)
+                .stdin(Stdio::piped())
+                .spawn()
+                .map_err(|e| {
+                    format!(
+                        "Could not run '{}' ({}); is ffmpeg installed?",
+                        self.config.ffmpeg, e
+                    )
+                })?;
+            self.child = Some(child);
+        }
+        let stdin = self
+            .child
+            .as_mut()
+            .and_then(|c| c.stdin.as_mut())
+            .ok_or("ffmpeg has already finished")?;
+        stdin
+            .write_all(image)
+            .map_err(|e| format!("Could not write to ffmpeg ({}); see its output above", e))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(mut child) = self.child.take() {
+            // Closing its input tells ffmpeg there are no more frames
+            drop(child.stdin.take());
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(format!("ffmpeg failed ({})", status).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes each frame to its own PNG file, and the settings it was recorded with to a file
+/// beside the first
+struct PngSequence {
+    template: String,
+    index: usize,
+    size: Option<(u32, u32)>,
+    settings: Option<String>,
+}
+
+impl PngSequence {
+    /// Files are named by filling in `{index}` in `path`, as in `text_format`. Without an
+    /// `{index}`, a five-digit index is added before the extension.
+    fn new(path: &Path) -> PngSequence {
+        let mut template = path.to_string_lossy().into_owned();
+        if !template.contains('{') {
+            let ext_len = path.extension().map_or(0, |ext| ext.len() + 1);
+            template.insert_str(template.len() - ext_len, "{index:05}");
+        }
+        PngSequence {
+            template,
+            index: 0,
+            size: None,
+            settings: None,
+        }
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        let filename = misc::format_template(&self.template, |name| match name {
+            "index" => Some(TemplateValue::Number(index as f64)),
+            _ => None,
+        });
+        PathBuf::from(filename)
+    }
+
+    /// Where the settings are kept: beside the first frame, with a `.settings` extension
+    fn settings_path(&self) -> PathBuf {
+        self.path(0).with_extension("settings")
+    }
+
+    /// Check that the existing frames were recorded with the current settings, if there are any
+    fn check_settings(&self) -> Result<(), Box<dyn Error>> {
+        let settings = match self.settings {
+            Some(ref settings) => settings,
+            None => return Ok(()),
+        };
+        let path = self.settings_path();
+        let recorded = fs::read_to_string(&path).map_err(|e| {
+            format!(
+                "Could not read the settings of the frames from '{}' ({}); record without --resume",
+                path.display(),
+                e
+            )
+        })?;
+        if recorded != *settings {
+            let msg = format!(
+                "The frames were recorded with other settings, listed in '{}'; record without \
+                 --resume",
+                path.display()
+            );
+            return Err(msg.into());
+        }
+        Ok(())
+    }
+}
+
+impl VideoEncoder for PngSequence {
+    fn add_frame(&mut self, image: &RgbImage) -> Result<(), Box<dyn Error>> {
+        check_size(&mut self.size, image)?;
+        let path = self.path(self.index);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        image.save(&path)?;
+        if self.index == 0 {
+            if let Some(ref settings) = self.settings {
+                fs::write(self.settings_path(), settings)?;
+            }
+        }
+        self.index += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn set_settings(&mut self, settings: &str) {
+        self.settings = Some(settings.to_string());
+    }
+
+    /// Skip the files that already exist, except the last, which may have been cut short.
+    /// Fails if they were recorded with other settings.
+    fn resume(&mut self) -> Result<usize, Box<dyn Error>> {
+        let mut count = 0;
+        while self.path(count).exists() {
+            count += 1;
+        }
+        if count > 0 {
+            self.check_settings()?;
+        }
+        self.index = count.saturating_sub(1);
+        Ok(self.index)
+    }
+}
+
+/// Writes an animated GIF, with a palette chosen for each frame
+struct GifEncoder {
+    file: Option<BufWriter<File>>,
+    encoder: Option<gif::Encoder<BufWriter<File>>>,
+    delay: u16,
+    loops: u16,
+    size: Option<(u32, u32)>,
+}
+
+impl VideoEncoder for GifEncoder {
+    fn add_frame(&mut self, image: &RgbImage) -> Result<(), Box<dyn Error>> {
+        check_size(&mut self.size, image)?;
+        let (width, height) = image.dimensions();
+        if width > u32::from(u16::MAX) || height > u32::from(u16::MAX) {
+            return Err(format!("{}x{} is too large for a GIF", width, height).into());
+        }
+        let (width, height) = (width as u16, height as u16);
+        if self.encoder.is_none() {
+            let file = self
+                .file
+                .take()
+                .ok_or("The GIF has already been finished")?;
+            let mut encoder = gif::Encoder::new(file, width, height, &[])?;
+            let repeat = match self.loops {
+                0 => gif::Repeat::Infinite,
+                n => gif::Repeat::Finite(n - 1),
+            };
+            encoder.set(repeat)?;
+            self.encoder = Some(encoder);
+        }
+        // Speed 10 of 30 quantizes much faster than 1, with little loss of quality
+        let mut frame = gif::Frame::from_rgb_speed(width, height, image, 10);
+        frame.delay = self.delay;
+        if let Some(ref mut encoder) = self.encoder {
+            encoder.write_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        // The encoder writes the end of the file when dropped
+        drop(self.encoder.take());
+        Ok(())
+    }
+}
+
+/// Write a PNG chunk: its length, type, data, and checksum
+fn png_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_all(&crc.sum().to_be_bytes())
+}
+
+/// Writes an animated PNG. The frame count is filled in when finished.
+struct ApngEncoder {
+    file: BufWriter<File>,
+    /// Where the animation control chunk starts
+    actl_offset: u64,
+    /// Sequence number of the next frame control or frame data chunk
+    sequence: u32,
+    frames: u32,
+    /// Frame delay, as a fraction of a second
+    delay: (u16, u16),
+    loops: u16,
+    size: Option<(u32, u32)>,
+}
+
+impl ApngEncoder {
+    fn new(path: &Path, fps: f32, loops: u16) -> Result<ApngEncoder, Box<dyn Error>> {
+        let file = BufWriter::new(File::create(path)?);
+        let fps = (fps * 100.).round().max(1.).min(f32::from(u16::MAX));
+        Ok(ApngEncoder {
+            file,
+            actl_offset: 0,
+            sequence: 0,
+            frames: 0,
+            delay: (100, fps as u16),
+            loops,
+            size: None,
+        })
+    }
+
+    fn animation_control(&self) -> Vec<u8> {
+        let mut data = self.frames.to_be_bytes().to_vec();
+        data.extend_from_slice(&u32::from(self.loops).to_be_bytes());
+        data
+    }
+
+    /// The PNG signature, header and animation control
+    fn write_header(&mut self, width: u32, height: u32) -> Result<(), Box<dyn Error>> {
+        self.file.write_all(b"\x89PNG\r\n\x1a\n")?;
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend_from_slice(&height.to_be_bytes());
+        // 8-bit RGB, no interlacing
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        png_chunk(&mut self.file, b"IHDR", &header)?;
+        self.actl_offset = self.file.stream_position()?;
+        let control = self.animation_control();
+        png_chunk(&mut self.file, b"acTL", &control)?;
+        Ok(())
+    }
+}
+
+impl VideoEncoder for ApngEncoder {
+    fn add_frame(&mut self, image: &RgbImage) -> Result<(), Box<dyn Error>> {
+        check_size(&mut self.size, image)?;
+        let (width, height) = image.dimensions();
+        if self.frames == 0 {
+            self.write_header(width, height)?;
+        }
+
+        let mut control = self.sequence.to_be_bytes().to_vec();
+        for &n in &[width, height, 0, 0] {
+            control.extend_from_slice(&n.to_be_bytes());
+        }
+        control.extend_from_slice(&self.delay.0.to_be_bytes());
+        control.extend_from_slice(&self.delay.1.to_be_bytes());
+        // Each frame replaces the last
+        control.extend_from_slice(&[0, 0]);
+        png_chunk(&mut self.file, b"fcTL", &control)?;
+        self.sequence += 1;
+
+        // Each row is stored as its difference from the row above ("Up" filtering)
+        let row = 3 * width as usize;
+        let raw: &[u8] = image;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for (y, pixels) in raw.chunks(row).enumerate() {
+            let mut filtered = Vec::with_capacity(row + 1);
+            filtered.push(2);
+            if y == 0 {
+                filtered.extend_from_slice(pixels);
+            } else {
+                let above = &raw[(y - 1) * row..y * row];
+                filtered.extend(pixels.iter().zip(above).map(|(&p, &a)| p.wrapping_sub(a)));
+            }
+            encoder.write_all(&filtered)?;
+        }
+        let compressed = encoder.finish()?;
+
+        if self.frames == 0 {
+            // The first frame is also the still image, for viewers without APNG support
+            png_chunk(&mut self.file, b"IDAT", &compressed)?;
+        } else {
+            let mut data = self.sequence.to_be_bytes().to_vec();
+            data.extend_from_slice(&compressed);
+            png_chunk(&mut self.file, b"fdAT", &data)?;
+            self.sequence += 1;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.frames == 0 {
+            return Err("No frames were recorded".into());
+        }
+        png_chunk(&mut self.file, b"IEND", &[])?;
+        let end = self.file.stream_position()?;
+        let _ = self.file.seek(SeekFrom::Start(self.actl_offset))?;
+        let control = self.animation_control();
+        png_chunk(&mut self.file, b"acTL", &control)?;
+        let _ = self.file.seek(SeekFrom::Start(end))?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Encodes with the `mpeg_encoder` library, started on the first frame
+#[cfg(feature = "mpeg")]
+struct MpegEncoder {
+    path: PathBuf,
+    fps: f32,
+    encoder: Option<mpeg_encoder::Encoder>,
+    size: Option<(u32, u32)>,
+}
+
+#[cfg(feature = "mpeg")]
+impl VideoEncoder for MpegEncoder {
+    fn add_frame(&mut self, image: &RgbImage) -> Result<(), Box<dyn Error>> {
+        check_size(&mut self.size, image)?;
+        let (width, height) = image.dimensions();
+        if self.encoder.is_none() {
+            self.encoder = Some(mpeg_encoder::Encoder::new_with_params(
+                &self.path,
+                width as usize,
+                height as usize,
+                None,                         // bit_rate
+                Some((1, self.fps as usize)), // time base
+                None,
+                None,
+                None,
+            ));
+        }
+        // The encoder takes rows bottom to top, as read from the window
+        let flipped = imageops::flip_vertical(image);
+        if let Some(ref mut encoder) = self.encoder {
+            encoder.encode_rgb(width as usize, height as usize, &flipped, false);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        // The encoder finishes the file when dropped
+        drop(self.encoder.take());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mpeg")]
+fn mpeg_encoder(path: &Path, fps: f32) -> Result<Box<dyn VideoEncoder>, Box<dyn Error>> {
+    Ok(Box::new(MpegEncoder {
+        path: path.to_path_buf(),
+        fps,
+        encoder: None,
+        size: None,
+    }))
+}
+
+#[cfg(not(feature = "mpeg"))]
+fn mpeg_encoder(_path: &Path, _fps: f32) -> Result<Box<dyn VideoEncoder>, Box<dyn Error>> {
+    Err("The mpeg format needs parview built with `--features mpeg`; try ffmpeg instead".into())
+}
+
+#[test]
+fn video_formats() {
+    let format = |name: &str| VideoFormat::from_path(Path::new(name));
+    assert_eq!(format("movie.mp4"), Some(VideoFormat::Ffmpeg));
+    assert_eq!(format("movie.WEBM"), Some(VideoFormat::Ffmpeg));
+    assert_eq!(format("frames/f.png"), Some(VideoFormat::Png));
+    assert_eq!(format("movie.gif"), Some(VideoFormat::Gif));
+    assert_eq!(format("movie.apng"), Some(VideoFormat::Apng));
+    assert_eq!(format("movie"), None);
+    assert_eq!("apng".parse(), Ok(VideoFormat::Apng));
+    assert!("avi".parse::<VideoFormat>().is_err());
+
+    let s = "
+        codec = \"libx264\"
+        crf = 20
+        args = [\"-preset\", \"slow\"]
+    ";
+    let config: VideoConfig = toml::from_str(s).unwrap();
+    let args = config.ffmpeg_args(641, 480, 24., Path::new("out.mp4"));
+    let args = args.join(" ");
+    assert!(args.starts_with("-y -loglevel error -f rawvideo -pixel_format rgb24"));
+    assert!(args.contains("-video_size 641x480 -framerate 24 -i -"));
+    assert!(args.contains("-c:v libx264 -crf 20 -pix_fmt yuv420p"));
+    assert!(args.contains("-vf pad=ceil(iw/2)*2:ceil(ih/2)*2"));
+    assert!(args.ends_with("-preset slow out.mp4"));
+
+    // Filters in `args` are kept, with the padding added after them
+    let config = VideoConfig {
+        args: vec!["-vf".into(), "scale=301:-1".into()],
+        ..VideoConfig::default()
+    };
+    let args = config.ffmpeg_args(641, 480, 24., Path::new("out.mp4"));
+    let filters: Vec<&String> = args.iter().filter(|a| a.contains("pad=")).collect();
+    assert_eq!(filters, vec!["scale=301:-1,pad=ceil(iw/2)*2:ceil(ih/2)*2"]);
+    assert_eq!(args.iter().filter(|a| *a == "-vf").count(), 1);
+
+    let sequence = PngSequence::new(Path::new("frames/movie.png"));
+    assert_eq!(sequence.path(12), PathBuf::from("frames/movie00012.png"));
+    let sequence = PngSequence::new(Path::new("frames/f{index:03}.png"));
+    assert_eq!(sequence.path(12), PathBuf::from("frames/f012.png"));
+}
+
+#[test]
+fn video_animations() {
+    use image::{AnimationDecoder, ImageDecoder, Rgb};
+
+    let dir = std::env::temp_dir().join(format!("parview-video-{}", std::process::id()));
+    let frames: Vec<RgbImage> = (0..3)
+        .map(|i| RgbImage::from_fn(5, 3, |x, y| Rgb([40 * i as u8, 50 * x as u8, 80 * y as u8])))
+        .collect();
+    let config = VideoConfig::default();
+    for name in &["movie.gif", "movie.apng"] {
+        let mut encoder = config.open(&dir.join(name), 10., None).unwrap();
+        for frame in &frames {
+            encoder.add_frame(frame).unwrap();
+        }
+        encoder.finish().unwrap();
+    }
+
+    let gif = image::gif::Decoder::new(File::open(dir.join("movie.gif")).unwrap()).unwrap();
+    assert_eq!(gif.into_frames().collect_frames().unwrap().len(), 3);
+
+    // An APNG is a PNG, with its first frame as the still image
+    let apng = fs::read(dir.join("movie.apng")).unwrap();
+    let still = image::load_from_memory_with_format(&apng, image::ImageFormat::PNG).unwrap();
+    assert_eq!(still.to_rgb().into_raw(), frames[0].clone().into_raw());
+    let png = image::png::PNGDecoder::new(&apng[..]).unwrap();
+    assert_eq!(png.dimensions(), (5, 3));
+    // The frame count was filled in: acTL holds 3 frames, looping forever
+    let actl = apng.windows(4).position(|w| w == b"acTL").unwrap();
+    assert_eq!(&apng[actl + 4..actl + 12], &[0, 0, 0, 3, 0, 0, 0, 0]);
+    assert_eq!(apng.windows(4).filter(|w| w == b"fdAT").count(), 2);
+
+    let mut resized = config.open(&dir.join("resized.apng"), 10., None).unwrap();
+    resized.add_frame(&frames[0]).unwrap();
+    assert!(resized.add_frame(&RgbImage::new(4, 4)).is_err());
+    assert!(resized.resume().is_err());
+
+    // Resuming a PNG sequence writes the last file found again, and carries on after it
+    let png = dir.join("png/frame.png");
+    let mut sequence = config.open(&png, 10., None).unwrap();
+    sequence.set_settings("stride = 1");
+    assert_eq!(sequence.resume().unwrap(), 0);
+    sequence.add_frame(&frames[0]).unwrap();
+    sequence.add_frame(&frames[1]).unwrap();
+    // ... but only if it was recorded the same way
+    let mut changed = config.open(&png, 10., None).unwrap();
+    changed.set_settings("stride = 2");
+    assert!(changed.resume().is_err());
+    let mut resumed = config.open(&png, 10., None).unwrap();
+    resumed.set_settings("stride = 1");
+    assert_eq!(resumed.resume().unwrap(), 1);
+    resumed.add_frame(&frames[1]).unwrap();
+    resumed.add_frame(&frames[2]).unwrap();
+    let last = image::open(dir.join("png/frame00002.png")).unwrap();
+    assert_eq!(last.to_rgb().into_raw(), frames[2].clone().into_raw());
+    fs::remove_dir_all(&dir).unwrap();
+}
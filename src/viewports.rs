@@ -0,0 +1,177 @@
+//! Several trajectories at once, each in its own part of the window.
+//!
+//! The window is drawn once per viewport: each pass is limited to the viewport's part of the
+//! window, and shows only that viewport's objects. See `camera::ViewCamera`.
+
+use std::error::Error;
+use std::path::Path;
+
+use na;
+
+use kiss3d::camera::ArcBall;
+use kiss3d::scene::SceneNode;
+use serde::{Deserialize, Serialize};
+
+use misc;
+use palette::Palette;
+
+#[cfg(test)]
+use toml;
+
+/// How the viewports are laid out, and what they share
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
+#[serde(default)]
+pub struct ViewportConfig {
+    /// Viewports in each row. None puts them all side by side.
+    pub columns: Option<usize>,
+    /// Move all the cameras together. Otherwise, the mouse moves the camera of the viewport
+    /// it is over, and only the first viewport follows the keys and the camera path.
+    pub linked: bool,
+    /// Palette files for the trajectories, in order. Trajectories without one use the
+    /// palette given on the command line, or the default.
+    pub palettes: Vec<String>,
+}
+
+impl Default for ViewportConfig {
+    fn default() -> Self {
+        ViewportConfig {
+            columns: None,
+            linked: true,
+            palettes: vec![],
+        }
+    }
+}
+
+/// A part of the window, in fractions of its width and height from the top left
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Rect {
+    /// Left edge
+    pub x: f32,
+    /// Top edge
+    pub y: f32,
+    /// Width
+    pub width: f32,
+    /// Height
+    pub height: f32,
+}
+
+impl Rect {
+    /// The whole window
+    pub fn full() -> Rect {
+        Rect {
+            x: 0.,
+            y: 0.,
+            width: 1.,
+            height: 1.,
+        }
+    }
+
+    /// Whether a point in the window frame (0 to 1, from the top left) is inside
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// The pixels of a window `width` by `height` inside, as (x, y, width, height) from the top
+    /// left. Edges are rounded, so that neighbouring rects meet exactly.
+    pub fn pixels(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let edge = |f: f32, size: u32| (f * size as f32).round() as u32;
+        let (x0, x1) = (edge(self.x, width), edge(self.x + self.width, width));
+        let (y0, y1) = (edge(self.y, height), edge(self.y + self.height, height));
+        (x0, y0, x1 - x0, y1 - y0)
+    }
+
+    /// A matrix that shrinks normalized device coordinates (-1 to 1, y up) of the whole
+    /// window into this rect
+    pub fn matrix(&self) -> na::Matrix4<f32> {
+        let mut m = na::Matrix4::identity();
+        m[(0, 0)] = self.width;
+        m[(1, 1)] = self.height;
+        m[(0, 3)] = 2. * self.x + self.width - 1.;
+        m[(1, 3)] = 1. - 2. * self.y - self.height;
+        m
+    }
+}
+
+impl ViewportConfig {
+    /// The parts of the window for `count` viewports, filling rows from the top left. The
+    /// viewports in a last row that is not full are widened to fill it.
+    pub fn layout(&self, count: usize) -> Vec<Rect> {
+        let count = count.max(1);
+        let columns = self.columns.unwrap_or(count).clamp(1, count);
+        let rows = (count + columns - 1) / columns;
+        let height = 1. / rows as f32;
+        (0..count)
+            .map(|i| {
+                let (row, column) = (i / columns, i % columns);
+                let in_row = columns.min(count - row * columns);
+                let width = 1. / in_row as f32;
+                Rect {
+                    x: column as f32 * width,
+                    y: row as f32 * height,
+                    width,
+                    height,
+                }
+            })
+            .collect()
+    }
+
+    /// The palette for the trajectory at `index`: its palette file if there is one, or else
+    /// `default`.
+    pub fn palette(&self, index: usize, default: &Palette) -> Result<Palette, Box<dyn Error>> {
+        match self.palettes.get(index) {
+            Some(fname) => misc::load_toml::<Palette>(Path::new(fname)),
+            None => Ok(default.clone()),
+        }
+    }
+}
+
+/// A part of the window showing one group of objects
+pub struct Viewport {
+    /// Where the viewport is in the window
+    pub rect: Rect,
+    /// The objects shown in this viewport, and hidden in the others
+    pub group: SceneNode,
+    /// The camera of this viewport. None uses the main camera.
+    pub arcball: Option<ArcBall>,
+}
+
+#[test]
+fn viewport_layout() {
+    let config = ViewportConfig::default();
+    assert_eq!(config.layout(1), vec![Rect::full()]);
+    let side_by_side = config.layout(2);
+    assert_eq!(side_by_side[1].x, 0.5);
+    assert_eq!(side_by_side[1].height, 1.);
+
+    let config: ViewportConfig = toml::from_str("columns = 2\nlinked = false").unwrap();
+    assert!(!config.linked);
+    let grid = config.layout(3);
+    assert_eq!(grid.len(), 3);
+    assert_eq!(grid[1].x, 0.5);
+    // The last row has room to spare, so its viewport is widened
+    assert_eq!(
+        grid[2],
+        Rect {
+            x: 0.,
+            y: 0.5,
+            width: 1.,
+            height: 0.5
+        }
+    );
+    assert!(grid[1].contains(0.75, 0.25));
+    assert!(!grid[1].contains(0.25, 0.75));
+    // Pixels of neighbouring viewports meet, even when the window does not divide evenly
+    let thirds = ViewportConfig::default().layout(3);
+    let pixels: Vec<_> = thirds.iter().map(|r| r.pixels(100, 50)).collect();
+    assert_eq!(
+        pixels,
+        vec![(0, 0, 33, 50), (33, 0, 34, 50), (67, 0, 33, 50)]
+    );
+
+    // The corners of the window land on the corners of the viewport
+    let m = grid[1].matrix();
+    let top_left = m * na::Vector4::new(-1., 1., 0., 1.);
+    let bottom_right = m * na::Vector4::new(1., -1., 0., 1.);
+    assert_eq!((top_left.x, top_left.y), (0., 1.));
+    assert_eq!((bottom_right.x, bottom_right.y), (1., 0.));
+}